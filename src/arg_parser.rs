@@ -1,5 +1,8 @@
-use clap::{Parser, Subcommand};
+use chrono::NaiveDate;
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
 use comprexor::CompressionLevel;
+use std::{ops::RangeInclusive, path::PathBuf};
+use transferhelper::SortBy;
 
 /// A simple way to use Transfer.sh from the CLI.
 #[derive(Parser)]
@@ -7,6 +10,14 @@ use comprexor::CompressionLevel;
 pub struct AppArguments {
     #[command(subcommand)]
     pub app_subcommands: Option<AppOptions>,
+
+    /// URL of the Transfer.sh server to use, overrides the `server_url` of the config file
+    #[arg(short = 'S', long, global = true, value_parser = validate_server_url)]
+    pub server: Option<String>,
+
+    /// Accept invalid TLS certificates, only meant for test servers
+    #[arg(long, global = true)]
+    pub insecure: bool,
 }
 
 #[derive(Subcommand)]
@@ -20,19 +31,162 @@ pub enum AppOptions {
         /// Show sha256sum
         #[arg(short, long, group = "list_flag")]
         sha256: bool,
+
+        /// Show the size, content type and original path of the files
+        #[arg(short = 'l', long)]
+        details: bool,
+
+        /// Output format, every format other than `table` includes all the fields of the entries
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+
+        #[command(flatten)]
+        filters: ListFilters,
+
+        /// Order of the entries, defaults to the `sort` of the config file or the id
+        #[arg(long, value_enum)]
+        sort: Option<SortField>,
+
+        /// Reverse the order of the entries
+        #[arg(short, long)]
+        reverse: bool,
+
+        /// Check the links on their servers with HEAD requests, showing their real status
+        #[arg(short, long)]
+        check: bool,
+
+        /// Save the status of the checked links in the database, see `status --cached`
+        #[arg(long, requires = "check")]
+        save: bool,
+
+        /// Maximum number of links checked at the same time
+        #[arg(short, long, default_value_t = 8, requires = "check", value_parser = clap::value_parser!(u16).range(1..))]
+        jobs: u16,
     },
 
-    /// Delete a file by id, deleting the file from Transfer.sh servers and the local database
-    Delete,
+    /// Check the links of the entries on their servers, showing whether they still exist
+    Status {
+        #[command(flatten)]
+        filters: ListFilters,
+
+        /// Show the status saved by the last check with `--save` instead of checking the links again
+        #[arg(long, conflicts_with_all = ["save", "jobs"])]
+        cached: bool,
 
-    /// Delete the local database but not the files on Transfer.sh servers
-    Drop,
+        /// Save the status of the checked links in the database
+        #[arg(long)]
+        save: bool,
+
+        /// Maximum number of links checked at the same time
+        #[arg(short, long, default_value_t = 8, value_parser = clap::value_parser!(u16).range(1..))]
+        jobs: u16,
+    },
+
+    /// Delete files by id, deleting the files from Transfer.sh servers and the local database
+    #[command(group(ArgGroup::new("bulk").multiple(true)))]
+    Delete {
+        /// Ids of the entries to delete, inclusive ranges like `3..7` are also accepted
+        #[arg(value_parser = validate_id_range, conflicts_with = "bulk")]
+        ids: Vec<RangeInclusive<i64>>,
+
+        /// Do not ask for confirmation, entries that fail to be deleted from the server are kept
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Delete every entry
+        #[arg(long, group = "bulk", conflicts_with_all = ["expired", "name"])]
+        all: bool,
+
+        /// Delete the entries that have already expired
+        #[arg(long, group = "bulk")]
+        expired: bool,
+
+        /// Delete the entries whose name matches this glob pattern, like `*.log`
+        #[arg(short, long, group = "bulk")]
+        name: Option<String>,
+
+        /// Match `--name` as a regular expression instead of a glob pattern
+        #[arg(long, requires = "name")]
+        regex: bool,
+
+        /// Also remove from the database the entries that fail to be deleted from the server
+        #[arg(short, long, requires = "bulk")]
+        force: bool,
+
+        /// Maximum number of entries deleted at the same time
+        #[arg(short, long, default_value_t = 4, requires = "bulk", value_parser = clap::value_parser!(u16).range(1..))]
+        jobs: u16,
+    },
+
+    /// Download a file by id or link, verifying its sha256sum when it is in the local database
+    Download {
+        /// Id or link of the entry to download
+        target: String,
+
+        /// Path where the file will be written, defaults to the file name of the link
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Overwrite the output file if it already exists
+        #[arg(short, long)]
+        force: bool,
+
+        /// Decrypt the file, only needed for links that are not in the local database
+        #[arg(short, long)]
+        decrypt: bool,
+
+        /// Password of a file encrypted by the server, prompted if the entry requires one
+        #[arg(long, env = "TRANSFERHELPER_SERVER_PASSWORD", hide_env_values = true)]
+        server_password: Option<String>,
+    },
+
+    /// Delete the local database but not the files on Transfer.sh servers, backing it up first
+    Drop {
+        /// Do not write a backup of the database to the config folder before deleting it
+        #[arg(long)]
+        no_backup: bool,
+    },
+
+    /// Replace the local database with a backup written by `drop`
+    Restore {
+        /// Path of the backup, or its file name inside the config folder
+        backup: PathBuf,
+
+        /// Do not ask for confirmation before replacing a database that has entries
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Remove the expired entries from the local database, their files are already gone from the servers
+    Prune {
+        /// Only remove the entries whose link is no longer on the server, checked with a HEAD request
+        #[arg(long)]
+        verify: bool,
+
+        /// Only show the entries that would be removed
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Maximum number of links verified at the same time
+        #[arg(short, long, default_value_t = 4, requires = "verify", value_parser = clap::value_parser!(u16).range(1..))]
+        jobs: u16,
+    },
+
+    /// Manage the local database
+    Db {
+        #[command(subcommand)]
+        command: DbOptions,
+    },
 
     /// Upload files to Transfer.sh servers
     Upload {
-        /// Path of the file to be uploaded
-        #[arg(value_parser = validate_path)]
-        path: String,
+        /// Paths of the files to be uploaded, glob patterns are also accepted, use `-` to read from stdin
+        #[arg(required = true, value_parser = validate_path)]
+        paths: Vec<String>,
 
         /// Compress the file or directory before uploading
         #[arg(short, long, group = "compress_flag")]
@@ -41,9 +195,118 @@ pub enum AppOptions {
         /// Compression level to be used, must be between 0 and 9
         #[arg(short, long, default_value = "6", requires = "compress_flag", value_parser = validate_compression_level)]
         level: CompressionLevel,
+
+        /// Number of days the file will be kept on the server
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        max_days: Option<u32>,
+
+        /// Number of downloads after which the file will be removed from the server
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        max_downloads: Option<u32>,
+
+        /// Name of the entry, skips the name prompt, only allowed when uploading a single file
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Do not prompt for anything, using the file name as the entry name if `--name` is not given
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Maximum number of files uploaded at the same time
+        #[arg(short, long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+        jobs: u16,
+
+        /// Encrypt the files with a passphrase before uploading, read from `TRANSFERHELPER_PASSPHRASE` or prompted
+        #[arg(short, long)]
+        encrypt: bool,
+
+        /// Password used by the server to encrypt the files, needed again to download them
+        #[arg(long, env = "TRANSFERHELPER_SERVER_PASSWORD", hide_env_values = true)]
+        server_password: Option<String>,
     },
 }
 
+/// Filters of the `list` command, the entries must match all of them.
+#[derive(Args)]
+pub struct ListFilters {
+    /// Only entries whose name matches this glob pattern, like `*.pdf`
+    #[arg(short, long)]
+    pub name: Option<String>,
+
+    /// Match `--name` as a regular expression instead of a glob pattern
+    #[arg(long, requires = "name")]
+    pub regex: bool,
+
+    /// Only entries that have not expired yet
+    #[arg(long, conflicts_with = "expired")]
+    pub available: bool,
+
+    /// Only entries that have already expired
+    #[arg(long)]
+    pub expired: bool,
+
+    /// Only entries uploaded on or after this date, as `YYYY-MM-DD` in UTC
+    #[arg(long, value_parser = validate_date)]
+    pub since: Option<NaiveDate>,
+
+    /// Only entries uploaded on or before this date, as `YYYY-MM-DD` in UTC
+    #[arg(long, value_parser = validate_date)]
+    pub until: Option<NaiveDate>,
+
+    /// Only entries whose sha256sum starts with this prefix
+    #[arg(long, value_parser = validate_sha256_prefix)]
+    pub sha256_prefix: Option<String>,
+
+    /// Maximum number of entries to show
+    #[arg(long)]
+    pub limit: Option<u32>,
+
+    /// Number of matching entries to skip
+    #[arg(long)]
+    pub offset: Option<u32>,
+}
+
+#[derive(Subcommand)]
+pub enum DbOptions {
+    /// Apply the pending schema migrations, they are also applied by every other command
+    Migrate {
+        /// Only show the pending migrations without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Clone, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+    Tsv,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SortField {
+    Id,
+    Name,
+    /// Upload date
+    Date,
+    Expiry,
+    /// Entries without a recorded size are ordered as the smallest ones
+    Size,
+}
+
+impl From<SortField> for SortBy {
+    fn from(field: SortField) -> SortBy {
+        match field {
+            SortField::Id => SortBy::Id,
+            SortField::Name => SortBy::Name,
+            SortField::Date => SortBy::Date,
+            SortField::Expiry => SortBy::Expiry,
+            SortField::Size => SortBy::Size,
+        }
+    }
+}
+
 fn validate_compression_level(level: &str) -> Result<CompressionLevel, String> {
     match level.parse::<u32>() {
         Ok(level) if (level <= 9) => Ok(CompressionLevel::Custom(level)),
@@ -54,9 +317,55 @@ fn validate_compression_level(level: &str) -> Result<CompressionLevel, String> {
 }
 
 fn validate_path(path: &str) -> Result<String, String> {
-    if std::path::Path::new(path).exists() {
+    let matches_glob = || glob::glob(path).is_ok_and(|mut paths| paths.next().is_some());
+    if path == "-" || std::path::Path::new(path).exists() || matches_glob() {
         Ok(path.to_string())
     } else {
         Err(format!("Provided path does not exist: `{path}`"))
     }
 }
+
+fn validate_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date: `{date}`, must be formatted as `YYYY-MM-DD`"))
+}
+
+fn validate_sha256_prefix(prefix: &str) -> Result<String, String> {
+    if !prefix.is_empty() && prefix.len() <= 64 && prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(prefix.to_string())
+    } else {
+        Err(format!(
+            "Invalid sha256 prefix: `{prefix}`, must be up to 64 hexadecimal characters"
+        ))
+    }
+}
+
+fn validate_server_url(url: &str) -> Result<String, String> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(url.to_string()),
+        _ => Err(format!(
+            "Invalid server URL: `{url}`, must be an http or https URL"
+        )),
+    }
+}
+
+pub fn validate_id_range(ids: &str) -> Result<RangeInclusive<i64>, String> {
+    let invalid = || format!("Invalid id: `{ids}`, must be a number or a range like `3..7`");
+    match ids.split_once("..") {
+        Some((start, end)) => {
+            let start = start.parse::<i64>().map_err(|_| invalid())?;
+            let end = end
+                .trim_start_matches('=')
+                .parse::<i64>()
+                .map_err(|_| invalid())?;
+            if start > end {
+                return Err(format!("Invalid range: `{ids}`, start is greater than end"));
+            }
+            Ok(start..=end)
+        }
+        None => {
+            let id = ids.parse::<i64>().map_err(|_| invalid())?;
+            Ok(id..=id)
+        }
+    }
+}
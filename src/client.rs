@@ -0,0 +1,537 @@
+use std::{
+    fmt,
+    fs::read_to_string,
+    future::Future,
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest::{
+    header::RETRY_AFTER, Certificate, Method, Proxy, RequestBuilder, Response, StatusCode,
+};
+use sha2::{Digest, Sha256};
+use tokio::{
+    io::{AsyncRead, AsyncWriteExt},
+    sync::oneshot,
+};
+use tokio_stream::StreamExt;
+use tokio_util::io::ReaderStream;
+
+use crate::{
+    crypto::{StreamDecryptor, StreamEncryptor},
+    errors::TransferError,
+    utils::{
+        Config, TransferProgress, TransferReporter, TransferResponse, UploadOptions, STDIN_PATH,
+    },
+};
+
+const UPLOAD_CHUNK_SIZE: usize = 64 * 1024;
+const REACHABILITY_TIMEOUT: Duration = Duration::from_secs(10);
+const PEM_CERTIFICATE_END: &str = "-----END CERTIFICATE-----";
+/// Longest wait accepted from a `Retry-After` header, the request is not retried if the server asks for more.
+/// The exponential backoff is capped to it as well.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(300);
+
+/// Response of an upload attempt and the receiver of the sha256sum and size of what was sent.
+type UploadAttempt = (
+    Result<Response, TransferError>,
+    oneshot::Receiver<(String, u64)>,
+);
+
+/// A failed attempt that will be sent again once `delay` has passed.
+struct Retry {
+    reason: String,
+    delay: Duration,
+    attempt: u32,
+    attempts: u32,
+}
+
+impl fmt::Display for Retry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, retrying in {:.1}s (attempt {} of {})",
+            self.reason,
+            self.delay.as_secs_f64(),
+            self.attempt,
+            self.attempts
+        )
+    }
+}
+
+/// Default reporter of the client, which ignores every event.
+struct SilentReporter;
+
+impl TransferReporter for SilentReporter {}
+
+/// HTTP client shared by every request sent to the Transfer.sh servers.
+///
+/// It is built once from the `Config`, so the proxy, timeouts, certificates and credentials
+/// apply the same way to uploads, downloads, deletes and reachability checks.
+pub struct TransferClient {
+    http: reqwest::Client,
+    config: Config,
+    reporter: Arc<dyn TransferReporter>,
+}
+
+impl TransferClient {
+    pub fn new(config: Config) -> Result<TransferClient, TransferError> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(proxy_url) = config.get_proxy_url() {
+            builder = builder
+                .proxy(Proxy::all(proxy_url).map_err(|err| format!("Invalid proxy URL: {err}"))?);
+        }
+        if let Some(connect_timeout) = config.get_connect_timeout() {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(ca_bundle) = config.get_ca_bundle() {
+            for certificate in read_ca_bundle(ca_bundle)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        if config.is_insecure() {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        Ok(TransferClient {
+            http: builder.build()?,
+            config,
+            reporter: Arc::new(SilentReporter),
+        })
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Sets who is told about the progress of the transfers and the retried requests.
+    pub fn set_reporter(&mut self, reporter: Arc<dyn TransferReporter>) {
+        self.reporter = reporter;
+    }
+
+    /// Decides whether the attempt number `attempt` is worth sending again.
+    ///
+    /// Only connection errors, timeouts and the status codes of an overloaded or restarting
+    /// server are retried, waiting for the `Retry-After` of the response if it has one or an
+    /// exponential backoff with jitter otherwise.
+    fn retry(&self, attempt: u32, result: &Result<Response, TransferError>) -> Option<Retry> {
+        let max_retries = self.config.get_max_retries();
+        if attempt > max_retries {
+            return None;
+        }
+
+        let (reason, retry_after) = match result {
+            Ok(response) if is_transient_status(response.status()) => (
+                format!("Status code: {}", response.status()),
+                retry_after(response),
+            ),
+            Err(TransferError::Request(err)) if err.is_connect() || err.is_timeout() => {
+                (err.to_string(), None)
+            }
+            _ => return None,
+        };
+
+        let delay = match retry_after {
+            Some(delay) if delay > MAX_RETRY_AFTER => return None,
+            Some(delay) => delay,
+            None => {
+                let backoff = self
+                    .config
+                    .get_retry_delay()
+                    .saturating_mul(2_u32.saturating_pow(attempt - 1))
+                    .min(MAX_RETRY_AFTER);
+                let jitter = rand::thread_rng().gen_range(0..=backoff.as_millis() as u64 / 2);
+                (backoff + Duration::from_millis(jitter)).min(MAX_RETRY_AFTER)
+            }
+        };
+
+        Some(Retry {
+            reason,
+            delay,
+            attempt: attempt + 1,
+            attempts: max_retries + 1,
+        })
+    }
+
+    /// Starts a request to `url`, authenticated with the credentials of its host if there are any.
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let request = self.http.request(method, url);
+        match self.config.credentials_for(url) {
+            Some((username, password)) => request.basic_auth(username, password),
+            None => request,
+        }
+    }
+
+    /// Awaits a read from the server, failing if nothing arrives within the configured read timeout.
+    async fn read<T>(
+        &self,
+        future: impl Future<Output = Result<T, reqwest::Error>>,
+    ) -> Result<T, TransferError> {
+        let Some(read_timeout) = self.config.get_read_timeout() else {
+            return Ok(future.await?);
+        };
+
+        match tokio::time::timeout(read_timeout, future).await {
+            Ok(result) => Ok(result?),
+            Err(_) => Err(format!(
+                "The server did not respond within {} seconds",
+                read_timeout.as_secs()
+            )
+            .into()),
+        }
+    }
+
+    /// Awaits the response to an upload, the read timeout only starts once the whole body was
+    /// handed to the connection, as sending a large file can take longer than the timeout.
+    async fn read_upload(
+        &self,
+        response: impl Future<Output = Result<Response, reqwest::Error>>,
+        body_sent: oneshot::Receiver<()>,
+    ) -> Result<Response, TransferError> {
+        tokio::pin!(response);
+        tokio::select! {
+            result = &mut response => return Ok(result?),
+            _ = body_sent => {}
+        }
+        self.read(response).await
+    }
+
+    /// Uploads a file, or stdin when `file_path` is `-`, calculating its sha256sum from the same
+    /// chunks that are sent to the server, so the input is read only once and never fully in memory.
+    ///
+    /// Transient failures are retried by reading the file again, which is not possible for stdin.
+    pub async fn upload(
+        &self,
+        file_path: &str,
+        file_name: &str,
+        options: &UploadOptions,
+        progress: Arc<TransferProgress>,
+    ) -> Result<TransferResponse, TransferError> {
+        let mut attempt = 1;
+        let (response, hash_receiver) = loop {
+            let sent = Arc::new(AtomicU64::new(0));
+            let (result, hash_receiver) = self
+                .send_upload(file_path, file_name, options, &progress, &sent)
+                .await?;
+            let retry = match file_path {
+                STDIN_PATH => None,
+                _ => self.retry(attempt, &result),
+            };
+            let Some(retry) = retry else {
+                break (result?, hash_receiver);
+            };
+
+            progress.rewind(sent.load(Ordering::Relaxed));
+            self.reporter.retry(&format!("{file_name}: {retry}"));
+            tokio::time::sleep(retry.delay).await;
+            attempt += 1;
+        };
+        ensure_authorized(&response)?;
+
+        if response.status() != StatusCode::OK {
+            return Err(
+                format!("Failed to upload file. Status code: {}", response.status()).into(),
+            );
+        }
+
+        let delete_link = response
+            .headers()
+            .get("x-url-delete")
+            .ok_or("No delete link found.")?
+            .to_str()?
+            .to_owned();
+        let (sha256sum, size) = hash_receiver
+            .await
+            .map_err(|_| "Failed to calculate the sha256sum of the uploaded file.")?;
+
+        Ok(TransferResponse {
+            transfer_link: response.text().await?,
+            delete_link,
+            sha256sum,
+            size,
+        })
+    }
+
+    /// Sends a single upload attempt, counting the bytes read in `sent` so they can be
+    /// removed from the progress if the attempt has to be repeated.
+    async fn send_upload(
+        &self,
+        file_path: &str,
+        file_name: &str,
+        options: &UploadOptions,
+        progress: &Arc<TransferProgress>,
+        sent: &Arc<AtomicU64>,
+    ) -> Result<UploadAttempt, TransferError> {
+        let reader: Box<dyn AsyncRead + Send + Sync + Unpin> = if file_path == STDIN_PATH {
+            Box::new(tokio::io::stdin())
+        } else {
+            Box::new(tokio::fs::File::open(file_path).await?)
+        };
+        let mut reader_stream = ReaderStream::with_capacity(reader, UPLOAD_CHUNK_SIZE);
+        let (hash_sender, hash_receiver) = oneshot::channel();
+        let (body_sent_sender, body_sent) = oneshot::channel();
+        let mut encryptor = options
+            .encryption_passphrase
+            .as_deref()
+            .map(StreamEncryptor::new)
+            .transpose()?;
+        let progress = Arc::clone(progress);
+        let reporter = Arc::clone(&self.reporter);
+        let sent = Arc::clone(sent);
+
+        // The sha256sum and size are always calculated from the plaintext, so downloads can verify it after decrypting.
+        let async_stream = async_stream::stream! {
+            let mut hasher = Sha256::new();
+            let mut size = 0;
+            if let Some(encryptor) = &encryptor {
+                yield Ok(Bytes::copy_from_slice(encryptor.header()));
+            }
+            while let Some(chunk) = reader_stream.next().await {
+                let chunk = chunk?;
+                hasher.update(&chunk);
+                size += chunk.len() as u64;
+                sent.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+                progress.advance(chunk.len() as u64);
+                reporter.progress(&progress);
+                match &mut encryptor {
+                    Some(encryptor) => yield encryptor.encrypt_chunk(&chunk).map(Bytes::from).map_err(io::Error::other),
+                    None => yield Ok(chunk),
+                }
+            }
+            if let Some(encryptor) = encryptor.take() {
+                yield encryptor.finish().map(Bytes::from).map_err(io::Error::other);
+            }
+            let _ = hash_sender.send((format!("{:x}", hasher.finalize()), size));
+            let _ = body_sent_sender.send(());
+        };
+
+        let mut request = self.request(
+            Method::PUT,
+            &server_endpoint(self.config.get_server_url(), file_name),
+        );
+        if let Some(max_days) = options.max_days {
+            request = request.header("Max-Days", max_days);
+        }
+        if let Some(max_downloads) = options.max_downloads {
+            request = request.header("Max-Downloads", max_downloads);
+        }
+        if let Some(server_password) = &options.server_password {
+            request = request.header("X-Encrypt-Password", server_password);
+        }
+
+        let result = self
+            .read_upload(
+                request
+                    .body(reqwest::Body::wrap_stream(async_stream))
+                    .send(),
+                body_sent,
+            )
+            .await;
+        Ok((result, hash_receiver))
+    }
+
+    /// Downloads `link` into `output_path`, returning the sha256sum calculated while writing it.
+    ///
+    /// The file is written next to the output with a `.part` suffix and only moved into place
+    /// once the download finishes and its sha256sum matches `expected_sha256sum`, if given.
+    pub async fn download(
+        &self,
+        link: &str,
+        output_path: &Path,
+        expected_sha256sum: Option<&str>,
+        decryption_passphrase: Option<String>,
+        server_password: Option<&str>,
+    ) -> Result<String, TransferError> {
+        let mut request = self.request(Method::GET, link);
+        if let Some(server_password) = server_password {
+            request = request.header("X-Decrypt-Password", server_password);
+        }
+        let response = self.read(request.send()).await?;
+        ensure_authorized(&response)?;
+        if response.status() != StatusCode::OK {
+            return Err(format!(
+                "Failed to download file. Status code: {}",
+                response.status()
+            )
+            .into());
+        }
+
+        let mut part_path = output_path.as_os_str().to_owned();
+        part_path.push(".part");
+        let part_path = PathBuf::from(part_path);
+        let sha256sum = match self
+            .write_download(response, &part_path, decryption_passphrase)
+            .await
+        {
+            Ok(sha256sum) => sha256sum,
+            Err(err) => {
+                let _ = tokio::fs::remove_file(&part_path).await;
+                return Err(err);
+            }
+        };
+
+        if let Some(expected_sha256sum) = expected_sha256sum {
+            if sha256sum != expected_sha256sum {
+                tokio::fs::remove_file(&part_path).await?;
+                return Err(format!(
+                    "Checksum mismatch, the downloaded file was discarded.\nExpected: {expected_sha256sum}\nReceived: {sha256sum}"
+                )
+                .into());
+            }
+        }
+        tokio::fs::rename(&part_path, output_path).await?;
+
+        Ok(sha256sum)
+    }
+
+    async fn write_download(
+        &self,
+        mut response: Response,
+        part_path: &Path,
+        decryption_passphrase: Option<String>,
+    ) -> Result<String, TransferError> {
+        let progress = TransferProgress::for_download(response.content_length());
+        let mut file = tokio::fs::File::create(part_path).await?;
+        let mut hasher = Sha256::new();
+        let mut decryptor = decryption_passphrase.map(StreamDecryptor::new);
+        while let Some(chunk) = self.read(response.chunk()).await? {
+            progress.advance(chunk.len() as u64);
+            self.reporter.progress(&progress);
+            let plaintext = match &mut decryptor {
+                Some(decryptor) => Bytes::from(decryptor.update(&chunk)?),
+                None => chunk,
+            };
+            hasher.update(&plaintext);
+            file.write_all(&plaintext).await?;
+        }
+        if let Some(decryptor) = decryptor {
+            decryptor.finish()?;
+        }
+        file.flush().await?;
+
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Deletes a file from the server with its delete link, a file that is already gone counts as deleted.
+    pub async fn delete(&self, delete_link: &str) -> Result<Response, TransferError> {
+        let mut attempt = 1;
+        let response = loop {
+            let result = self
+                .read(self.request(Method::DELETE, delete_link).send())
+                .await;
+            let Some(retry) = self.retry(attempt, &result) else {
+                break result?;
+            };
+
+            self.reporter
+                .retry(&format!("Delete of {delete_link}: {retry}"));
+            tokio::time::sleep(retry.delay).await;
+            attempt += 1;
+        };
+        ensure_authorized(&response)?;
+
+        match response.status() {
+            StatusCode::OK | StatusCode::NOT_FOUND => Ok(response),
+            _ => Err(format!("Failed to delete entry. Status code: {}", response.status()).into()),
+        }
+    }
+
+    pub async fn status(&self, server_url: &str) -> Result<StatusCode, TransferError> {
+        let request = self
+            .request(Method::GET, server_url)
+            .timeout(REACHABILITY_TIMEOUT);
+
+        Ok(self.read(request.send()).await?.status())
+    }
+
+    /// Status code of a HEAD request to `link`, telling whether the server still has the file.
+    pub async fn link_status(&self, link: &str) -> Result<StatusCode, TransferError> {
+        let request = self
+            .request(Method::HEAD, link)
+            .timeout(REACHABILITY_TIMEOUT);
+
+        Ok(self.read(request.send()).await?.status())
+    }
+
+    /// Fails with [`TransferError::Unreachable`] unless the server answers with a success status.
+    pub async fn check_reachable(&self, server_url: &str) -> Result<(), TransferError> {
+        match self.status(server_url).await {
+            Ok(StatusCode::OK) => Ok(()),
+            Ok(code @ (StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)) => {
+                Err(TransferError::Unauthorized(server_url.to_string(), code))
+            }
+            Ok(code) => Err(TransferError::Unreachable(format!("status code: {code}"))),
+            Err(err) => Err(TransferError::Unreachable(err.to_string())),
+        }
+    }
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::REQUEST_TIMEOUT
+            | StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Parses a `Retry-After` header, given either as a number of seconds or as an HTTP date.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        date.with_timezone(&Utc)
+            .signed_duration_since(Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// Reads every certificate of a PEM file, which may hold a whole chain or bundle.
+fn read_ca_bundle(path: &Path) -> Result<Vec<Certificate>, TransferError> {
+    let bundle = read_to_string(path)
+        .map_err(|err| format!("Failed to read the CA bundle `{}`: {err}", path.display()))?;
+
+    let certificates = bundle
+        .split_inclusive(PEM_CERTIFICATE_END)
+        .filter(|pem| pem.contains(PEM_CERTIFICATE_END))
+        .map(|pem| {
+            Certificate::from_pem(pem.trim().as_bytes())
+                .map_err(|err| format!("Invalid certificate in `{}`: {err}", path.display()).into())
+        })
+        .collect::<Result<Vec<_>, TransferError>>()?;
+
+    if certificates.is_empty() {
+        return Err(format!("No certificate found in `{}`", path.display()).into());
+    }
+    Ok(certificates)
+}
+
+fn ensure_authorized(response: &Response) -> Result<(), TransferError> {
+    match response.status() {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(TransferError::Unauthorized(
+            response.url().origin().ascii_serialization(),
+            response.status(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn server_endpoint(server_url: &str, file_name: &str) -> String {
+    format!("{}/{}", server_url.trim_end_matches('/'), file_name)
+}
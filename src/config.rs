@@ -0,0 +1,52 @@
+use dirs::config_dir;
+use std::{
+    fs::{create_dir_all, read_to_string, write},
+    io,
+    path::PathBuf,
+};
+use transferhelper::{Config, TransferError};
+
+/// Reads the config file, writing the default one if it does not exist yet.
+///
+/// An invalid file is reported instead of being replaced, it may hold credentials.
+pub fn get_config() -> Result<Config, TransferError> {
+    let config_path = config_app_folder()?.join("transfer-helper-config.json");
+    let config = match read_to_string(&config_path) {
+        Ok(config) => config,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            let default_config = Config::default();
+            write(config_path, serde_json::to_string_pretty(&default_config)?)?;
+            return Ok(default_config);
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_str(&config)).map_err(
+        |err| {
+            // The path is `.` or `?` when the error is not about a single field, like a syntax error.
+            let field = match err.path().to_string() {
+                path if path == "." || path == "?" => String::new(),
+                path => format!(", field `{path}`"),
+            };
+            format!(
+                "Invalid config file {}{field}: {}",
+                config_path.display(),
+                err.inner()
+            )
+            .into()
+        },
+    )
+}
+
+pub fn create_config_app_folder() -> Result<(), io::Error> {
+    create_dir_all(config_app_folder()?)?;
+    Ok(())
+}
+
+pub fn config_app_folder() -> Result<PathBuf, io::Error> {
+    let config_path = config_dir().ok_or(io::Error::new(
+        io::ErrorKind::NotFound,
+        "Config directory not found",
+    ))?;
+    Ok(config_path.join("transfer-sh-helper"))
+}
@@ -0,0 +1,246 @@
+use aes_gcm::{
+    aead::{
+        rand_core::RngCore,
+        stream::{DecryptorBE32, EncryptorBE32},
+        KeyInit, OsRng,
+    },
+    Aes256Gcm,
+};
+use argon2::Argon2;
+
+use crate::errors::TransferError;
+
+// Layout of an encrypted file: `MAGIC | salt | nonce prefix`, followed by frames made of a
+// big endian `u32` length and the ciphertext of a chunk. The last frame has `LAST_FRAME` set.
+const MAGIC: &[u8] = b"THENC1";
+const SALT_LEN: usize = 16;
+const NONCE_PREFIX_LEN: usize = 7;
+const HEADER_LEN: usize = MAGIC.len() + SALT_LEN + NONCE_PREFIX_LEN;
+const FRAME_HEADER_LEN: usize = 4;
+const LAST_FRAME: u32 = 1 << 31;
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+pub struct StreamEncryptor {
+    header: Vec<u8>,
+    encryptor: EncryptorBE32<Aes256Gcm>,
+}
+
+impl StreamEncryptor {
+    pub fn new(passphrase: &str) -> Result<StreamEncryptor, TransferError> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce_prefix);
+
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&salt);
+        header.extend_from_slice(&nonce_prefix);
+
+        Ok(StreamEncryptor {
+            header,
+            encryptor: EncryptorBE32::from_aead(
+                derive_cipher(passphrase, &salt)?,
+                nonce_prefix.as_ref().into(),
+            ),
+        })
+    }
+
+    /// Bytes that must be sent before any encrypted chunk.
+    pub fn header(&self) -> &[u8] {
+        &self.header
+    }
+
+    pub fn encrypt_chunk(&mut self, chunk: &[u8]) -> Result<Vec<u8>, TransferError> {
+        let ciphertext = self
+            .encryptor
+            .encrypt_next(chunk)
+            .map_err(|_| "Failed to encrypt the file")?;
+        frame(&ciphertext, false)
+    }
+
+    /// Closes the stream, a file missing this last frame is rejected when decrypting.
+    pub fn finish(self) -> Result<Vec<u8>, TransferError> {
+        let ciphertext = self
+            .encryptor
+            .encrypt_last(&[][..])
+            .map_err(|_| "Failed to encrypt the file")?;
+        frame(&ciphertext, true)
+    }
+}
+
+pub struct StreamDecryptor {
+    passphrase: String,
+    buffer: Vec<u8>,
+    decryptor: Option<DecryptorBE32<Aes256Gcm>>,
+    finished: bool,
+}
+
+impl StreamDecryptor {
+    pub fn new(passphrase: String) -> StreamDecryptor {
+        StreamDecryptor {
+            passphrase,
+            buffer: vec![],
+            decryptor: None,
+            finished: false,
+        }
+    }
+
+    /// Feeds downloaded bytes, returning the plaintext of every frame completed by them.
+    pub fn update(&mut self, data: &[u8]) -> Result<Vec<u8>, TransferError> {
+        self.buffer.extend_from_slice(data);
+        let mut plaintext = vec![];
+        let mut consumed = 0;
+
+        if self.decryptor.is_none() {
+            if self.buffer.len() < HEADER_LEN {
+                return Ok(plaintext);
+            }
+            let (magic, rest) = self.buffer[..HEADER_LEN].split_at(MAGIC.len());
+            if magic != MAGIC {
+                return Err("The file was not encrypted by transferhelper".into());
+            }
+            let (salt, nonce_prefix) = rest.split_at(SALT_LEN);
+            self.decryptor = Some(DecryptorBE32::from_aead(
+                derive_cipher(&self.passphrase, salt)?,
+                nonce_prefix.into(),
+            ));
+            consumed = HEADER_LEN;
+        }
+
+        while self.buffer.len() - consumed >= FRAME_HEADER_LEN {
+            if self.finished {
+                return Err("Unexpected data after the end of the encrypted file".into());
+            }
+            let length_bytes: [u8; FRAME_HEADER_LEN] = self.buffer
+                [consumed..consumed + FRAME_HEADER_LEN]
+                .try_into()
+                .map_err(|_| "Invalid encrypted frame")?;
+            let length = u32::from_be_bytes(length_bytes);
+            let is_last = length & LAST_FRAME != 0;
+            let length = usize::try_from(length & !LAST_FRAME)?;
+            if length > MAX_FRAME_LEN {
+                return Err("Invalid encrypted frame".into());
+            }
+            let start = consumed + FRAME_HEADER_LEN;
+            if self.buffer.len() < start + length {
+                break;
+            }
+
+            let ciphertext = &self.buffer[start..start + length];
+            let decrypted = if is_last {
+                self.finished = true;
+                self.decryptor
+                    .take()
+                    .ok_or("Invalid encrypted frame")?
+                    .decrypt_last(ciphertext)
+            } else {
+                self.decryptor
+                    .as_mut()
+                    .ok_or("Invalid encrypted frame")?
+                    .decrypt_next(ciphertext)
+            }
+            .map_err(|_| "Failed to decrypt the file, wrong passphrase or corrupted data")?;
+            plaintext.extend_from_slice(&decrypted);
+            consumed = start + length;
+        }
+        self.buffer.drain(..consumed);
+
+        Ok(plaintext)
+    }
+
+    /// Fails if the download ended before the last frame, meaning the file was truncated.
+    pub fn finish(self) -> Result<(), TransferError> {
+        if !self.finished || !self.buffer.is_empty() {
+            return Err("The encrypted file is truncated".into());
+        }
+        Ok(())
+    }
+}
+
+fn derive_cipher(passphrase: &str, salt: &[u8]) -> Result<Aes256Gcm, TransferError> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| format!("Failed to derive the encryption key: {err}"))?;
+    Ok(Aes256Gcm::new(&key.into()))
+}
+
+fn frame(ciphertext: &[u8], is_last: bool) -> Result<Vec<u8>, TransferError> {
+    let mut length = u32::try_from(ciphertext.len())?;
+    if is_last {
+        length |= LAST_FRAME;
+    }
+    let mut framed = Vec::with_capacity(FRAME_HEADER_LEN + ciphertext.len());
+    framed.extend_from_slice(&length.to_be_bytes());
+    framed.extend_from_slice(ciphertext);
+    Ok(framed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encrypt(passphrase: &str, chunks: &[&[u8]]) -> Vec<u8> {
+        let mut encryptor = StreamEncryptor::new(passphrase).unwrap();
+        let mut encrypted = encryptor.header().to_vec();
+        for chunk in chunks {
+            encrypted.extend(encryptor.encrypt_chunk(chunk).unwrap());
+        }
+        encrypted.extend(encryptor.finish().unwrap());
+        encrypted
+    }
+
+    /// Feeds `data` in pieces of `piece_len` bytes, like a download would.
+    fn decrypt(passphrase: &str, data: &[u8], piece_len: usize) -> Result<Vec<u8>, TransferError> {
+        let mut decryptor = StreamDecryptor::new(passphrase.to_string());
+        let mut plaintext = vec![];
+        for piece in data.chunks(piece_len) {
+            plaintext.extend(decryptor.update(piece)?);
+        }
+        decryptor.finish()?;
+        Ok(plaintext)
+    }
+
+    #[test]
+    fn round_trip_across_chunk_splits() {
+        let chunks: [&[u8]; 3] = [b"first chunk", b"", b"and the last one"];
+        let encrypted = encrypt("secret", &chunks);
+
+        for piece_len in [1, 3, FRAME_HEADER_LEN, HEADER_LEN + 1, encrypted.len()] {
+            let plaintext = decrypt("secret", &encrypted, piece_len).unwrap();
+            assert_eq!(plaintext, chunks.concat(), "pieces of {piece_len} bytes");
+        }
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let encrypted = encrypt("secret", &[b"some data"]);
+
+        assert!(decrypt("not the secret", &encrypted, encrypted.len()).is_err());
+    }
+
+    #[test]
+    fn truncated_file_is_rejected() {
+        let encrypted = encrypt("secret", &[b"some data", b"more data"]);
+
+        for len in [
+            0,
+            HEADER_LEN,
+            HEADER_LEN + FRAME_HEADER_LEN,
+            encrypted.len() - 1,
+        ] {
+            assert!(
+                decrypt("secret", &encrypted[..len], 1).is_err(),
+                "truncated to {len} bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn empty_input_round_trips() {
+        let encrypted = encrypt("secret", &[]);
+
+        assert_eq!(decrypt("secret", &encrypted, 1).unwrap(), b"");
+    }
+}
@@ -1,68 +1,383 @@
-use std::{fs::remove_file, path::PathBuf, sync::Arc};
-use tokio::try_join;
+use futures::{stream, StreamExt};
+use regex::Regex;
+use rusqlite::{functions::FunctionFlags, named_params, params_from_iter, OpenFlags, ToSql};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{copy, remove_file, rename},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use crate::{
+    client::TransferClient,
     errors::TransferError,
     utils::{
-        ask_confirmation, calculate_sha25sum, config_app_folder, create_config_app_folder,
-        current_time, delete_entry_server, get_config, upload_file, Link,
+        current_time, Link, TransferProgress, TransferResponse, UploadOptions, DEFAULT_SERVER_URL,
+        ENCRYPTED_EXTENSION, STDIN_PATH, UNIX_DAY, UNIX_WEEK,
     },
 };
 
-pub struct Database {
+pub enum DeleteStatus {
+    Deleted,
+    NotFound,
+}
+
+/// How the name of the entries is matched when listing them.
+#[derive(PartialEq)]
+pub enum NamePattern {
+    /// Case sensitive glob pattern, like `*.pdf`.
+    Glob(String),
+    Regex(String),
+}
+
+/// Field the listed entries are ordered by, ties are ordered by id.
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    #[default]
+    Id,
+    Name,
+    /// Upload date.
+    Date,
+    Expiry,
+    /// Entries without a recorded size are ordered as the smallest ones.
+    Size,
+}
+
+/// Filters and order of the listed entries, applied in the SQL query. Empty fields match every entry.
+#[derive(Default, PartialEq)]
+pub struct EntryQuery {
+    pub name: Option<NamePattern>,
+    /// Only entries that are still available, or only the expired ones.
+    pub available: Option<bool>,
+    /// Unix time from which the entries were uploaded, inclusive.
+    pub uploaded_since: Option<u64>,
+    /// Unix time before which the entries were uploaded, exclusive.
+    pub uploaded_before: Option<u64>,
+    pub sha256_prefix: Option<String>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    pub sort: SortBy,
+    /// Descending order instead of ascending.
+    pub reverse: bool,
+}
+
+impl EntryQuery {
+    /// Whether any field other than the order can leave entries out.
+    pub fn is_filtered(&self) -> bool {
+        let EntryQuery {
+            name,
+            available,
+            uploaded_since,
+            uploaded_before,
+            sha256_prefix,
+            limit,
+            offset,
+            sort: _,
+            reverse: _,
+        } = self;
+        name.is_some()
+            || available.is_some()
+            || uploaded_since.is_some()
+            || uploaded_before.is_some()
+            || sha256_prefix.is_some()
+            || limit.is_some()
+            || offset.is_some()
+    }
+}
+
+/// The sqlite database where the links of every upload are kept.
+pub struct Store {
     connection: rusqlite::Connection,
     database_path: PathBuf,
 }
 
-impl Database {
-    pub fn new() -> Result<Database, TransferError> {
-        create_config_app_folder()?;
+/// A schema change, the database `user_version` is the number of migrations already applied.
+struct Migration {
+    description: &'static str,
+    apply: fn(&rusqlite::Transaction) -> Result<(), TransferError>,
+}
+
+/// Every schema change in the order they are applied, only append new ones to the end.
+///
+/// The first migrations existed before the database was versioned, so they are written to also
+/// work on databases where some of their columns were already added.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "Create the transfer_data table",
+        apply: |transaction| {
+            transaction.execute(
+                "
+                CREATE TABLE IF NOT EXISTS transfer_data (
+                'id'	INTEGER,
+                'name'	TEXT,
+                'link'	TEXT,
+                'deleteLink'	TEXT,
+                'unixTime'	INTEGER,
+                PRIMARY KEY('id' AUTOINCREMENT));
+                ",
+                (),
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "Add the sha256sum column",
+        apply: |transaction| {
+            add_column_if_missing(transaction, "sha256sum", "TEXT")?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "Add the server column",
+        apply: |transaction| {
+            if add_column_if_missing(transaction, "server", "TEXT")? {
+                // Entries created before this column existed were all uploaded to the public instance.
+                transaction.execute(
+                    "UPDATE transfer_data SET server = ? WHERE server IS NULL",
+                    [DEFAULT_SERVER_URL],
+                )?;
+            }
+            Ok(())
+        },
+    },
+    Migration {
+        description: "Add the maxDays column",
+        apply: |transaction| {
+            add_column_if_missing(transaction, "maxDays", "INTEGER")?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "Add the encrypted column",
+        apply: |transaction| {
+            add_column_if_missing(transaction, "encrypted", "INTEGER NOT NULL DEFAULT 0")?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "Add the passwordProtected column",
+        apply: |transaction| {
+            add_column_if_missing(
+                transaction,
+                "passwordProtected",
+                "INTEGER NOT NULL DEFAULT 0",
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "Add the size, contentType and originalPath columns",
+        apply: |transaction| {
+            transaction.execute_batch(
+                "
+                ALTER TABLE transfer_data ADD COLUMN 'size' INTEGER;
+                ALTER TABLE transfer_data ADD COLUMN 'contentType' TEXT;
+                ALTER TABLE transfer_data ADD COLUMN 'originalPath' TEXT;
+                ",
+            )?;
+            Ok(())
+        },
+    },
+    Migration {
+        description: "Add the serverStatus and checkedTime columns",
+        apply: |transaction| {
+            transaction.execute_batch(
+                "
+                ALTER TABLE transfer_data ADD COLUMN 'serverStatus' INTEGER;
+                ALTER TABLE transfer_data ADD COLUMN 'checkedTime' INTEGER;
+                ",
+            )?;
+            Ok(())
+        },
+    },
+];
+
+fn add_column_if_missing(
+    connection: &rusqlite::Connection,
+    column: &str,
+    kind: &str,
+) -> Result<bool, TransferError> {
+    let count = connection.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('transfer_data') WHERE name = ?",
+        [column],
+        |row| row.get::<_, i64>(0),
+    )?;
+
+    if count != 0 {
+        return Ok(false);
+    }
+
+    connection.execute(
+        &format!("ALTER TABLE transfer_data ADD COLUMN '{column}' {kind};"),
+        (),
+    )?;
+
+    Ok(true)
+}
+
+impl Store {
+    /// Opens the database at `database_path`, creating it and applying any pending migration.
+    pub fn open(database_path: impl AsRef<Path>) -> Result<Store, TransferError> {
+        let mut store = Store::open_without_migrating(database_path)?;
+        store.migrate()?;
 
-        let binding = get_config()?;
-        let database_file = binding.get_database_file();
+        Ok(store)
+    }
 
-        let database_path = config_app_folder()?.join(database_file);
-        Ok(Database {
+    /// Opens the database at `database_path` as it is, see [`Store::pending_migrations`].
+    pub fn open_without_migrating(database_path: impl AsRef<Path>) -> Result<Store, TransferError> {
+        let database_path = database_path.as_ref().to_path_buf();
+        let store = Store {
             connection: rusqlite::Connection::open(&database_path)?,
             database_path,
-        })
-    }
-
-    pub fn create_table(&self) -> Result<(), TransferError> {
-        self.connection.execute(
-            "
-            CREATE TABLE IF NOT EXISTS transfer_data (
-            'id'	INTEGER,
-            'name'	TEXT,
-            'link'	TEXT,
-            'deleteLink'	TEXT,
-            'unixTime'	INTEGER,
-            PRIMARY KEY('id' AUTOINCREMENT));
-            ",
-            (),
-        )?;
+        };
+        store.register_regexp()?;
+        // Fails early with a clear error when the database was created by a newer version.
+        store.schema_version()?;
+
+        Ok(store)
+    }
 
-        let count = self.connection.query_row(
-            "SELECT COUNT(*) FROM pragma_table_info('transfer_data') WHERE name = 'sha256sum'",
-            [],
-            |row| row.get::<_, i64>(0),
+    /// Defines the `REGEXP` operator, which sqlite leaves to the application.
+    fn register_regexp(&self) -> Result<(), TransferError> {
+        self.connection.create_scalar_function(
+            "regexp",
+            2,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |context| {
+                // The pattern is compiled once per query instead of once per row.
+                let regex = context.get_or_create_aux(0, |pattern| {
+                    Regex::new(pattern.as_str()?)
+                        .map_err(|err| rusqlite::Error::UserFunctionError(err.into()))
+                })?;
+                let text = context.get_raw(1).as_str_or_null()?.unwrap_or_default();
+                Ok(regex.is_match(text))
+            },
         )?;
 
-        if count == 0 {
-            self.connection.execute(
-                "
-                    ALTER TABLE transfer_data ADD COLUMN 'sha256sum' TEXT;
-                    ",
-                (),
-            )?;
+        Ok(())
+    }
+
+    /// Version of the database schema, the number of migrations applied to it.
+    pub fn schema_version(&self) -> Result<usize, TransferError> {
+        let version: usize = self
+            .connection
+            .pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+        if version > MIGRATIONS.len() {
+            return Err(format!(
+                "The database schema version {version} is newer than the latest supported ({}), update transferhelper",
+                MIGRATIONS.len()
+            )
+            .into());
         }
+        Ok(version)
+    }
 
-        Ok(())
+    /// Versions and descriptions of the migrations not yet applied to the database.
+    pub fn pending_migrations(&self) -> Result<Vec<(usize, &'static str)>, TransferError> {
+        let version = self.schema_version()?;
+        Ok(MIGRATIONS
+            .iter()
+            .enumerate()
+            .skip(version)
+            .map(|(index, migration)| (index + 1, migration.description))
+            .collect())
+    }
+
+    /// Applies the pending migrations in a single transaction, returning the ones applied.
+    ///
+    /// The database is left untouched if any of them fails.
+    pub fn migrate(&mut self) -> Result<Vec<(usize, &'static str)>, TransferError> {
+        let pending = self.pending_migrations()?;
+        if pending.is_empty() {
+            return Ok(pending);
+        }
+
+        let transaction = self.connection.transaction()?;
+        for &(version, description) in &pending {
+            (MIGRATIONS[version - 1].apply)(&transaction)
+                .map_err(|err| format!("Migration {version} ({description}) failed: {err}"))?;
+        }
+        transaction.pragma_update(None, "user_version", MIGRATIONS.len())?;
+        transaction.commit()?;
+
+        Ok(pending)
     }
 
     pub fn get_all_entries(&self) -> Result<Vec<Link>, TransferError> {
-        let mut stmt = self.connection.prepare("SELECT * FROM transfer_data")?;
-        let mut rows = stmt.query([])?;
+        self.get_entries(&EntryQuery::default())
+    }
+
+    /// Entries matching every filter of `query`, in its order.
+    pub fn get_entries(&self, query: &EntryQuery) -> Result<Vec<Link>, TransferError> {
+        // Same expiration as `Link`, entries without `maxDays` follow the server default retention.
+        let expire_time = format!("(unixTime + COALESCE(maxDays * {UNIX_DAY}, {UNIX_WEEK}))");
+        let mut conditions = vec![];
+        let mut params: Vec<Box<dyn ToSql>> = vec![];
+
+        match &query.name {
+            Some(NamePattern::Glob(pattern)) => {
+                conditions.push("name GLOB ?".to_string());
+                params.push(Box::new(pattern.clone()));
+            }
+            Some(NamePattern::Regex(pattern)) => {
+                conditions.push("name REGEXP ?".to_string());
+                params.push(Box::new(pattern.clone()));
+            }
+            None => {}
+        }
+        if let Some(available) = query.available {
+            let operator = if available { ">" } else { "<=" };
+            conditions.push(format!("{expire_time} {operator} ?"));
+            params.push(Box::new(current_time()?));
+        }
+        if let Some(since) = query.uploaded_since {
+            conditions.push("unixTime >= ?".to_string());
+            params.push(Box::new(since));
+        }
+        if let Some(before) = query.uploaded_before {
+            conditions.push("unixTime < ?".to_string());
+            params.push(Box::new(before));
+        }
+        if let Some(prefix) = &query.sha256_prefix {
+            conditions.push("substr(sha256sum, 1, ?) = ?".to_string());
+            params.push(Box::new(prefix.len()));
+            params.push(Box::new(prefix.to_lowercase()));
+        }
+
+        let mut sql = String::from("SELECT * FROM transfer_data");
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        let order_by = match query.sort {
+            SortBy::Id => vec!["id"],
+            SortBy::Name => vec!["name COLLATE NOCASE", "id"],
+            SortBy::Date => vec!["unixTime", "id"],
+            SortBy::Expiry => vec![expire_time.as_str(), "id"],
+            SortBy::Size => vec!["size", "id"],
+        };
+        let direction = if query.reverse { " DESC" } else { "" };
+        sql.push_str(" ORDER BY ");
+        sql.push_str(
+            &order_by
+                .iter()
+                .map(|term| format!("{term}{direction}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        if query.limit.is_some() || query.offset.is_some() {
+            // sqlite only accepts an offset after a limit, a negative one means no limit.
+            sql.push_str(" LIMIT ? OFFSET ?");
+            params.push(Box::new(query.limit.map_or(-1, i64::from)));
+            params.push(Box::new(query.offset.unwrap_or_default()));
+        }
+
+        let mut stmt = self.connection.prepare(&sql)?;
+        let mut rows = stmt.query(params_from_iter(params.iter()))?;
 
         let mut result: Vec<Link> = vec![];
         while let Some(row) = rows.next()? {
@@ -71,95 +386,285 @@ impl Database {
         Ok(result)
     }
 
+    /// Uploads `file_path` and records it as a new entry. `source_path` is stored as the
+    /// original path, it differs from `file_path` when the upload is an archive made from it.
     pub async fn transfer_file(
         &self,
         entry_name: &str,
         file_path: &str,
-    ) -> Result<(), TransferError> {
-        let arc_file_path = Arc::new(file_path.to_string());
-        let upload_handle = tokio::spawn(upload_file(Arc::clone(&arc_file_path)));
-        let sha256sum_handle = tokio::spawn(calculate_sha25sum(Arc::clone(&arc_file_path)));
-        let (transfer_response, file_hash) =
-            try_join!(upload_handle, sha256sum_handle).map_err(|err| err.to_string())?;
-        let transfer_response = transfer_response?;
-        let file_hash = file_hash?;
+        source_path: &str,
+        client: &TransferClient,
+        options: &UploadOptions,
+        progress: Arc<TransferProgress>,
+    ) -> Result<i64, TransferError> {
+        let (mut file_name, original_path) = if file_path == STDIN_PATH {
+            (entry_name.replace('/', "_"), None)
+        } else {
+            let file_name = file_path
+                .split('/')
+                .next_back()
+                .ok_or("Failed to get file name from upload URL.")?
+                .to_string();
+            let original_path = std::fs::canonicalize(source_path)?;
+            (
+                file_name,
+                Some(original_path.to_string_lossy().into_owned()),
+            )
+        };
+        // Guessed before the encrypted extension is added, so it describes the original file.
+        let content_type = mime_guess::from_path(&file_name)
+            .first()
+            .map(|mime| mime.to_string());
+        if options.encryption_passphrase.is_some() {
+            file_name.push_str(ENCRYPTED_EXTENSION);
+        }
+        let transfer_response = client
+            .upload(file_path, &file_name, options, progress)
+            .await?;
         self.insert_entry(
             entry_name,
-            &transfer_response.transfer_link,
-            &transfer_response.delete_link,
-            &file_hash,
-        )?;
-
-        Ok(())
+            &transfer_response,
+            client.config().get_server_url(),
+            options,
+            content_type.as_deref(),
+            original_path.as_deref(),
+        )
     }
 
     pub fn insert_entry(
         &self,
         name: &str,
-        link: &str,
-        delete_link: &str,
-        sha256sum: &str,
-    ) -> Result<(), TransferError> {
-        let current_time = &current_time()?.to_string();
-        let query = "INSERT INTO transfer_data (name, link, deleteLink, unixTime, sha256sum) VALUES (:name, :link, :deleteLink, :unixTime, :sha256sum)";
-        let query_params = &[
-            (":name", name),
-            (":link", link),
-            (":deleteLink", delete_link),
-            (":unixTime", current_time),
-            (":sha256sum", sha256sum),
-        ];
+        transfer_response: &TransferResponse,
+        server: &str,
+        options: &UploadOptions,
+        content_type: Option<&str>,
+        original_path: Option<&str>,
+    ) -> Result<i64, TransferError> {
+        let current_time = current_time()?;
+        let query = "INSERT INTO transfer_data (name, link, deleteLink, unixTime, sha256sum, server, maxDays, encrypted, passwordProtected, size, contentType, originalPath) VALUES (:name, :link, :deleteLink, :unixTime, :sha256sum, :server, :maxDays, :encrypted, :passwordProtected, :size, :contentType, :originalPath)";
+        let query_params = named_params! {
+            ":name": name,
+            ":link": transfer_response.transfer_link,
+            ":deleteLink": transfer_response.delete_link,
+            ":unixTime": current_time,
+            ":sha256sum": transfer_response.sha256sum,
+            ":server": server,
+            ":maxDays": options.max_days,
+            ":encrypted": options.encryption_passphrase.is_some(),
+            ":passwordProtected": options.server_password.is_some(),
+            ":size": transfer_response.size,
+            ":contentType": content_type,
+            ":originalPath": original_path,
+        };
 
         let mut stmt = self.connection.prepare(query)?;
         stmt.execute(query_params)?;
 
+        Ok(self.connection.last_insert_rowid())
+    }
+
+    /// Writes a consistent copy of the database to `backup_path`, which must not exist.
+    pub fn backup_to(&self, backup_path: impl AsRef<Path>) -> Result<(), TransferError> {
+        let backup_path = backup_path.as_ref();
+        if backup_path.exists() {
+            return Err(format!("The backup file {} already exists", backup_path.display()).into());
+        }
+        self.connection
+            .execute("VACUUM INTO ?", [backup_path.to_string_lossy()])?;
+
         Ok(())
     }
 
-    pub fn delete_database_file(&self) -> Result<(), TransferError> {
-        if !ask_confirmation("Are you sure you want to delete the database file?")? {
-            return Ok(());
+    /// Checks that `backup_path` is an intact database of transferhelper, returning its number
+    /// of entries. The file is only opened for reading.
+    pub fn validate_backup(backup_path: impl AsRef<Path>) -> Result<usize, TransferError> {
+        let backup_path = backup_path.as_ref();
+        if !backup_path.is_file() {
+            return Err(format!("The backup file {} does not exist", backup_path.display()).into());
+        }
+        let invalid = |reason: &str| -> TransferError {
+            format!("{} is not a valid backup: {reason}", backup_path.display()).into()
+        };
+
+        let connection =
+            rusqlite::Connection::open_with_flags(backup_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let integrity: String = connection
+            .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+            .map_err(|err| invalid(&err.to_string()))?;
+        if integrity != "ok" {
+            return Err(invalid(&integrity));
+        }
+        let version: usize =
+            connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version > MIGRATIONS.len() {
+            return Err(invalid(&format!(
+                "its schema version {version} is newer than the latest supported ({})",
+                MIGRATIONS.len()
+            )));
+        }
+        let entries: usize = connection
+            .query_row("SELECT COUNT(*) FROM transfer_data", [], |row| row.get(0))
+            .map_err(|_| invalid("it has no transfer_data table"))?;
+
+        Ok(entries)
+    }
+
+    /// Replaces the database with a copy of `backup_path`, returning the restored database with
+    /// any pending migration applied. The current database is left untouched if anything fails.
+    pub fn restore(self, backup_path: impl AsRef<Path>) -> Result<Store, TransferError> {
+        let backup_path = backup_path.as_ref();
+        Store::validate_backup(backup_path)?;
+
+        // Copied next to the database first, so the database is swapped with a single rename.
+        let mut restoring_path = self.database_path.clone().into_os_string();
+        restoring_path.push(".restoring");
+        let restoring_path = PathBuf::from(restoring_path);
+        copy(backup_path, &restoring_path)?;
+
+        let database_path = self.database_path.clone();
+        self.connection.close().map_err(|(_, err)| err)?;
+        if let Err(err) = rename(&restoring_path, &database_path) {
+            let _ = remove_file(&restoring_path);
+            return Err(err.into());
         }
+
+        Store::open(database_path)
+    }
+
+    /// Closes the database and removes its file.
+    pub fn delete_database_file(self) -> Result<(), TransferError> {
+        self.connection.close().map_err(|(_, err)| err)?;
         remove_file(&self.database_path)?;
-        println!("Database file deleted.\n");
         Ok(())
     }
 
-    pub async fn delete_entry(&mut self, entry_id: i64) -> Result<(), TransferError> {
+    /// Deletes the entry from the server and then from the local database.
+    ///
+    /// The entry is kept if the server delete fails, [`Store::remove_entry`] removes it
+    /// from the database alone.
+    pub async fn delete_entry(
+        &mut self,
+        entry_id: i64,
+        client: &TransferClient,
+    ) -> Result<DeleteStatus, TransferError> {
         let delete_link = if let Some(link) = self.get_single_entry(entry_id)? {
             link.get_delete_link().to_string()
         } else {
-            println!("\nEntry with id {entry_id} not found.\n");
-            return Ok(());
+            return Ok(DeleteStatus::NotFound);
         };
-        if !ask_confirmation(&format!(
-            "Are you sure you want to delete the entry {entry_id}? (It will also delete from the cloud)"
-        ))? {
-            return Ok(());
-        }
 
         let query = "DELETE FROM transfer_data WHERE id = ?";
         let transaction = self.connection.transaction()?;
         transaction.prepare(query)?.execute([&entry_id])?;
 
-        match delete_entry_server(&delete_link).await {
+        match client.delete(&delete_link).await {
             Ok(_) => {
                 transaction.commit()?;
-                println!("Entry with id {entry_id} deleted.\n");
+                Ok(DeleteStatus::Deleted)
             }
             Err(err) => {
-                eprintln!("Error while deleting entry from server: {err}");
-                if ask_confirmation("Do you want to delete the entry from the database anyway? (It will still be accessible from the link)")? {
-                    transaction.commit()?;
-                    println!("Entry with id {entry_id} deleted.\n");
-                } else {
-                    transaction.rollback()?;
-                    println!("Entry with id {entry_id} not deleted.\n");
-                }
+                transaction.rollback()?;
+                Err(format!("Error while deleting entry from server: {err}").into())
             }
         }
+    }
 
-        Ok(())
+    /// Deletes the entries from their servers with up to `jobs` concurrent requests, returning the
+    /// result of each one in the same order.
+    ///
+    /// Only the entries deleted from the server are removed from the local database, unless
+    /// `force` is set, in which case all of them are.
+    pub async fn delete_entries(
+        &mut self,
+        entries: Vec<Link>,
+        client: &TransferClient,
+        jobs: usize,
+        force: bool,
+    ) -> Result<Vec<(Link, Result<(), TransferError>)>, TransferError> {
+        let mut results: Vec<_> = stream::iter(entries.into_iter().enumerate())
+            .map(|(index, entry)| async move {
+                let result = match client.delete(entry.get_delete_link()).await {
+                    Ok(_) => Ok(()),
+                    Err(err) => {
+                        Err(format!("Error while deleting entry from server: {err}").into())
+                    }
+                };
+                (index, entry, result)
+            })
+            .buffer_unordered(jobs)
+            .collect()
+            .await;
+        results.sort_unstable_by_key(|(index, _, _)| *index);
+
+        let removed_ids: Vec<i64> = results
+            .iter()
+            .filter(|(_, _, result)| force || result.is_ok())
+            .map(|(_, entry, _)| entry.get_id())
+            .collect();
+        self.remove_entries(&removed_ids)?;
+
+        Ok(results
+            .into_iter()
+            .map(|(_, entry, result)| (entry, result))
+            .collect())
+    }
+
+    /// Removes the entry from the local database only, returning whether it existed.
+    pub fn remove_entry(&self, entry_id: i64) -> Result<bool, TransferError> {
+        let removed = self
+            .connection
+            .execute("DELETE FROM transfer_data WHERE id = ?", [&entry_id])?;
+        Ok(removed != 0)
+    }
+
+    /// Checks the links of `entries` on their servers with up to `jobs` concurrent HEAD requests,
+    /// returning them in the same order with the status of the check.
+    ///
+    /// The results are also saved in the database when `save` is set.
+    pub async fn check_entries(
+        &self,
+        entries: Vec<Link>,
+        client: &TransferClient,
+        jobs: usize,
+        save: bool,
+    ) -> Result<Vec<Link>, TransferError> {
+        let checked_time = current_time()?;
+        let mut checked: Vec<(usize, Link)> = stream::iter(entries.into_iter().enumerate())
+            .map(|(index, mut entry)| async move {
+                // Unreachable servers are recorded without a status code.
+                let status = client.link_status(entry.get_link()).await.ok();
+                entry.set_check(status.map(|status| status.as_u16()), checked_time);
+                (index, entry)
+            })
+            .buffer_unordered(jobs)
+            .collect()
+            .await;
+        checked.sort_unstable_by_key(|(index, _)| *index);
+
+        if save {
+            let transaction = self.connection.unchecked_transaction()?;
+            for (_, entry) in &checked {
+                transaction.execute(
+                    "UPDATE transfer_data SET serverStatus = ?, checkedTime = ? WHERE id = ?",
+                    (entry.get_server_status(), checked_time, entry.get_id()),
+                )?;
+            }
+            transaction.commit()?;
+        }
+
+        Ok(checked.into_iter().map(|(_, entry)| entry).collect())
+    }
+
+    /// Removes the entries from the local database only, all of them or none.
+    pub fn remove_entries(&mut self, entry_ids: &[i64]) -> Result<usize, TransferError> {
+        let transaction = self.connection.transaction()?;
+        let mut removed = 0;
+        for entry_id in entry_ids {
+            removed += transaction.execute("DELETE FROM transfer_data WHERE id = ?", [entry_id])?;
+        }
+        transaction.commit()?;
+
+        Ok(removed)
     }
 
     pub fn get_single_entry(&self, entry_id: i64) -> Result<Option<Link>, TransferError> {
@@ -175,4 +680,137 @@ impl Database {
         }
         Ok(None)
     }
+
+    pub fn get_entry_by_link(&self, link: &str) -> Result<Option<Link>, TransferError> {
+        let mut stmt = self
+            .connection
+            .prepare("SELECT * FROM transfer_data WHERE link = ?")?;
+        let mut rows = stmt.query([link])?;
+
+        if let Some(row) = rows.next()? {
+            return Ok(Some(Link::new(row)?));
+        }
+        Ok(None)
+    }
+
+    /// Entries whose id is inside any of `ranges`, ordered by id.
+    pub fn get_entries_in_ranges(
+        &self,
+        ranges: &[RangeInclusive<i64>],
+    ) -> Result<Vec<Link>, TransferError> {
+        if ranges.is_empty() {
+            return Ok(vec![]);
+        }
+        let conditions = vec!["id BETWEEN ? AND ?"; ranges.len()].join(" OR ");
+        let params = ranges
+            .iter()
+            .flat_map(|range| [*range.start(), *range.end()]);
+
+        let mut stmt = self.connection.prepare(&format!(
+            "SELECT * FROM transfer_data WHERE {conditions} ORDER BY id"
+        ))?;
+        let mut rows = stmt.query(params_from_iter(params))?;
+
+        let mut result = vec![];
+        while let Some(row) = rows.next()? {
+            result.push(Link::new(row)?);
+        }
+        Ok(result)
+    }
+
+    pub fn get_entries_by_ids(&self, entry_ids: &[i64]) -> Result<Vec<Link>, TransferError> {
+        let mut result = vec![];
+        for entry_id in entry_ids {
+            if let Some(link) = self.get_single_entry(*entry_id)? {
+                result.push(link);
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Columns in the positions `Link::new` reads them from.
+    const COLUMNS: [&str; 15] = [
+        "id",
+        "name",
+        "link",
+        "deleteLink",
+        "unixTime",
+        "sha256sum",
+        "server",
+        "maxDays",
+        "encrypted",
+        "passwordProtected",
+        "size",
+        "contentType",
+        "originalPath",
+        "serverStatus",
+        "checkedTime",
+    ];
+
+    fn columns(store: &Store) -> Vec<String> {
+        let mut statement = store
+            .connection
+            .prepare("SELECT name FROM pragma_table_info('transfer_data') ORDER BY cid")
+            .unwrap();
+        statement
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn migrates_an_empty_database() {
+        let mut store = Store::open_without_migrating(":memory:").unwrap();
+        assert_eq!(store.schema_version().unwrap(), 0);
+
+        assert_eq!(store.migrate().unwrap().len(), MIGRATIONS.len());
+        assert_eq!(store.schema_version().unwrap(), MIGRATIONS.len());
+        assert_eq!(columns(&store), COLUMNS);
+        assert!(store.get_all_entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn migrates_a_baseline_database() {
+        let mut store = Store::open_without_migrating(":memory:").unwrap();
+        // Schema of the releases without migrations, which left `user_version` at 0.
+        store
+            .connection
+            .execute_batch(
+                "
+                CREATE TABLE transfer_data (
+                'id'	INTEGER,
+                'name'	TEXT,
+                'link'	TEXT,
+                'deleteLink'	TEXT,
+                'unixTime'	INTEGER,
+                'sha256sum'	TEXT,
+                PRIMARY KEY('id' AUTOINCREMENT));
+                INSERT INTO transfer_data (name, link, deleteLink, unixTime, sha256sum)
+                VALUES ('file.txt', 'https://transfer.sh/a/file.txt',
+                    'https://transfer.sh/a/file.txt/del', 1700000000, 'abc');
+                ",
+            )
+            .unwrap();
+
+        store.migrate().unwrap();
+        assert_eq!(store.schema_version().unwrap(), MIGRATIONS.len());
+        assert_eq!(columns(&store), COLUMNS);
+
+        let entries = store.get_all_entries().unwrap();
+        let [entry] = entries.as_slice() else {
+            panic!("expected a single entry, got {}", entries.len());
+        };
+        assert_eq!(entry.get_link(), "https://transfer.sh/a/file.txt");
+        assert_eq!(entry.get_sha256sum(), Some("abc"));
+        assert_eq!(entry.get_server(), DEFAULT_SERVER_URL);
+        assert!(!entry.is_encrypted());
+        assert_eq!(entry.get_size(), None);
+        assert_eq!(entry.get_expire_time(), 1_700_000_000 + UNIX_WEEK);
+    }
 }
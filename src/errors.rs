@@ -5,6 +5,7 @@ use std::{
 
 use reqwest::header::ToStrError;
 
+/// Errors reaching `main` end the process with the code given by [`TransferError::exit_code`].
 #[derive(Debug)]
 pub enum TransferError {
     Io(std::io::Error),
@@ -12,7 +13,35 @@ pub enum TransferError {
     Generic(String),
     Database(rusqlite::Error),
     Serialization(serde_json::Error),
-    AsyncMutex(tokio::sync::TryLockError),
+    /// The server rejected the credentials, or none were given, with a 401 or 403.
+    Unauthorized(String, reqwest::StatusCode),
+    /// The server could not be reached or answered the reachability check with an error.
+    Unreachable(String),
+    /// No entry matches the id given by the user.
+    NotFound(i64),
+    /// The user declined a confirmation prompt.
+    Aborted,
+}
+
+impl TransferError {
+    /// Exit code of the process for this error, `2` is left to the argument errors of clap.
+    ///
+    /// | Code | Meaning                                   |
+    /// |------|-------------------------------------------|
+    /// | 1    | Any other error                           |
+    /// | 3    | The server is not reachable               |
+    /// | 4    | The server rejected the credentials       |
+    /// | 5    | The entry was not found                   |
+    /// | 6    | The operation was aborted by the user     |
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            TransferError::Unreachable(_) => 3,
+            TransferError::Unauthorized(..) => 4,
+            TransferError::NotFound(_) => 5,
+            TransferError::Aborted => 6,
+            _ => 1,
+        }
+    }
 }
 
 impl std::error::Error for TransferError {}
@@ -25,23 +54,29 @@ impl std::fmt::Display for TransferError {
             TransferError::Generic(err) => write!(f, "{err}"),
             TransferError::Database(err) => write!(f, "{err}"),
             TransferError::Serialization(err) => write!(f, "{err}"),
-            TransferError::AsyncMutex(err) => write!(f, "{err}"),
+            TransferError::Unauthorized(host, status) => write!(
+                f,
+                "Authentication failed for {host}, status code: {status}. Check the `username` and `password` of the config file, the `TRANSFERHELPER_USERNAME` and `TRANSFERHELPER_PASSWORD` environment variables or your netrc file"
+            ),
+            TransferError::Unreachable(err) => write!(f, "Transfer.sh is not reachable: {err}"),
+            TransferError::NotFound(entry_id) => write!(f, "Entry with id {entry_id} not found"),
+            TransferError::Aborted => write!(f, "Operation aborted"),
         }
     }
 }
 
-impl From<tokio::sync::TryLockError> for TransferError {
-    fn from(err: tokio::sync::TryLockError) -> Self {
-        TransferError::AsyncMutex(err)
-    }
-}
-
 impl From<serde_json::Error> for TransferError {
     fn from(err: serde_json::Error) -> Self {
         TransferError::Serialization(err)
     }
 }
 
+impl From<csv::Error> for TransferError {
+    fn from(err: csv::Error) -> Self {
+        TransferError::Generic(err.to_string())
+    }
+}
+
 impl From<rusqlite::Error> for TransferError {
     fn from(err: rusqlite::Error) -> Self {
         TransferError::Database(err)
@@ -0,0 +1,18 @@
+//! Upload files to Transfer.sh and keep track of their links.
+//!
+//! [`TransferClient`] talks to the Transfer.sh servers and [`Store`] keeps the uploaded links
+//! in a sqlite database, both are configured explicitly so they can be used from other tools.
+
+mod client;
+mod crypto;
+mod database;
+mod errors;
+mod utils;
+
+pub use client::TransferClient;
+pub use database::{DeleteStatus, EntryQuery, NamePattern, SortBy, Store};
+pub use errors::TransferError;
+pub use utils::{
+    current_time, get_file_size, readable_size, Config, Link, TransferProgress, TransferReporter,
+    TransferResponse, UploadOptions, DEFAULT_SERVER_URL, ENCRYPTED_EXTENSION, STDIN_PATH,
+};
@@ -1,6 +1,6 @@
 #[macro_export]
 macro_rules! transfer_table {
-    ($data:expr, $del_links:expr, $show_sha256:expr) => {
+    ($data:expr, $columns:expr) => {
         use comfy_table::modifiers::UTF8_ROUND_CORNERS;
         use comfy_table::presets::UTF8_FULL;
         use comfy_table::{Cell, CellAlignment, Color, ContentArrangement, Table};
@@ -10,83 +10,71 @@ macro_rules! transfer_table {
             .load_preset(UTF8_FULL)
             .apply_modifier(UTF8_ROUND_CORNERS)
             .set_content_arrangement(ContentArrangement::Dynamic);
-        if $del_links {
-            table.set_header([
-                Cell::new("ID").set_alignment(CellAlignment::Center),
-                Cell::new("Name"),
-                Cell::new("Delete Link"),
-                Cell::new("Expire Date").set_alignment(CellAlignment::Center),
-                Cell::new("Available").set_alignment(CellAlignment::Center),
-            ]);
-            for entry in $data {
-                table.add_row([
-                    Cell::new(entry.id).set_alignment(CellAlignment::Center),
-                    Cell::new(entry.name),
-                    Cell::new(entry.delete_link),
-                    Cell::new(readable_date(entry.unix_time)?).set_alignment(CellAlignment::Center),
-                    if entry.is_available {
-                        Cell::new(entry.is_available)
-                            .fg(Color::Green)
-                            .set_alignment(CellAlignment::Center)
-                    } else {
-                        Cell::new(entry.is_available)
-                            .fg(Color::Red)
-                            .set_alignment(CellAlignment::Center)
-                    },
-                ]);
+
+        let mut header = vec![
+            Cell::new("ID").set_alignment(CellAlignment::Center),
+            Cell::new("Name"),
+        ];
+        if $columns.delete_link {
+            header.push(Cell::new("Delete Link"));
+        } else if $columns.sha256 {
+            header.push(Cell::new("SHA256"));
+            header.push(Cell::new("Link"));
+        } else {
+            header.push(Cell::new("Link"));
+        }
+        if $columns.details {
+            header.push(Cell::new("Size").set_alignment(CellAlignment::Right));
+            header.push(Cell::new("Type"));
+            header.push(Cell::new("Original Path"));
+        }
+        header.push(Cell::new("Expire Date").set_alignment(CellAlignment::Center));
+        header.push(Cell::new("Available").set_alignment(CellAlignment::Center));
+        if $columns.server_status {
+            header.push(Cell::new("Server"));
+        }
+        table.set_header(header);
+
+        for entry in $data {
+            let mut row = vec![
+                Cell::new(entry.get_id()).set_alignment(CellAlignment::Center),
+                Cell::new(entry.display_name()),
+            ];
+            if $columns.delete_link {
+                row.push(Cell::new(entry.get_delete_link()));
+            } else if $columns.sha256 {
+                row.push(Cell::new(entry.get_sha256sum().unwrap_or_default()));
+                row.push(Cell::new(entry.get_link()));
+            } else {
+                row.push(Cell::new(entry.get_link()));
             }
-        } else if $show_sha256 {
-            table.set_header([
-                Cell::new("ID").set_alignment(CellAlignment::Center),
-                Cell::new("Name"),
-                Cell::new("SHA256"),
-                Cell::new("Link"),
-                Cell::new("Expire Date").set_alignment(CellAlignment::Center),
-                Cell::new("Available").set_alignment(CellAlignment::Center),
-            ]);
-            for entry in $data {
-                table.add_row([
-                    Cell::new(entry.id).set_alignment(CellAlignment::Center),
-                    Cell::new(entry.name),
-                    Cell::new(entry.sha256sum.unwrap_or("".to_string())),
-                    Cell::new(entry.link),
-                    Cell::new(readable_date(entry.unix_time)?).set_alignment(CellAlignment::Center),
-                    if entry.is_available {
-                        Cell::new(entry.is_available)
-                            .fg(Color::Green)
-                            .set_alignment(CellAlignment::Center)
-                    } else {
-                        Cell::new(entry.is_available)
-                            .fg(Color::Red)
-                            .set_alignment(CellAlignment::Center)
-                    },
-                ]);
+            if $columns.details {
+                row.push(Cell::new(entry.readable_size()).set_alignment(CellAlignment::Right));
+                row.push(Cell::new(entry.get_content_type().unwrap_or_default()));
+                row.push(Cell::new(entry.get_original_path().unwrap_or_default()));
             }
-        } else {
-            table.set_header([
-                Cell::new("ID").set_alignment(CellAlignment::Center),
-                Cell::new("Name"),
-                Cell::new("Link"),
-                Cell::new("Expire Date").set_alignment(CellAlignment::Center),
-                Cell::new("Available").set_alignment(CellAlignment::Center),
-            ]);
-            for entry in $data {
-                table.add_row([
-                    Cell::new(entry.id).set_alignment(CellAlignment::Center),
-                    Cell::new(entry.name),
-                    Cell::new(entry.link),
-                    Cell::new(readable_date(entry.unix_time)?).set_alignment(CellAlignment::Center),
-                    if entry.is_available {
-                        Cell::new(entry.is_available)
-                            .fg(Color::Green)
-                            .set_alignment(CellAlignment::Center)
-                    } else {
-                        Cell::new(entry.is_available)
-                            .fg(Color::Red)
-                            .set_alignment(CellAlignment::Center)
-                    },
-                ]);
+            row.push(
+                Cell::new(readable_date(entry.get_expire_time())?)
+                    .set_alignment(CellAlignment::Center),
+            );
+            row.push(if entry.is_available() {
+                Cell::new(entry.is_available())
+                    .fg(Color::Green)
+                    .set_alignment(CellAlignment::Center)
+            } else {
+                Cell::new(entry.is_available())
+                    .fg(Color::Red)
+                    .set_alignment(CellAlignment::Center)
+            });
+            if $columns.server_status {
+                let color = match entry.get_server_status() {
+                    Some(200..=299) => Color::Green,
+                    Some(404 | 410) => Color::Red,
+                    _ => Color::Yellow,
+                };
+                row.push(Cell::new(entry.server_status_label()).fg(color));
             }
+            table.add_row(row);
         }
         println!("{table}");
     };
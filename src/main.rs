@@ -1,219 +1,987 @@
 mod arg_parser;
-mod database;
-mod errors;
+mod config;
 mod macros;
-mod utils;
+mod terminal;
 use std::{
-    io::{self, Write},
-    path::Path,
-    process::exit,
+    collections::HashSet,
+    env,
+    io::{self, IsTerminal, Write},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
 };
 
-use arg_parser::{AppArguments, AppOptions};
+use arg_parser::{AppArguments, AppOptions, DbOptions, ListFilters, OutputFormat};
+use chrono::{NaiveDate, NaiveTime, Utc};
 use clap::Parser;
 use comprexor::{CompressionLevel, Compressor};
-use database::Database;
-use errors::TransferError;
-use once_cell::sync::Lazy;
+use config::{config_app_folder, create_config_app_folder, get_config};
+use futures::{stream, StreamExt};
 use owo_colors::OwoColorize;
+use regex::Regex;
 use reqwest::StatusCode;
-use tokio::sync::Mutex;
-use utils::transfer_response_code;
+use terminal::{ask_confirmation, Columns, TerminalReporter};
+use transferhelper::{
+    get_file_size, DeleteStatus, EntryQuery, Link, NamePattern, SortBy, Store, TransferClient,
+    TransferError, TransferProgress, UploadOptions, ENCRYPTED_EXTENSION, STDIN_PATH,
+};
+
+/// Environment variable read before prompting for the encryption passphrase.
+const PASSPHRASE_ENV: &str = "TRANSFERHELPER_PASSPHRASE";
+
+async fn execute_delete_by_id(
+    ids: Vec<RangeInclusive<i64>>,
+    assume_yes: bool,
+    store: &mut Store,
+    client: &TransferClient,
+) -> Result<(), TransferError> {
+    let ids = if ids.is_empty() {
+        let Some(ids) = ask_entry_ids(store)? else {
+            println!("No data to delete");
+            return Ok(());
+        };
+        ids
+    } else {
+        ids
+    };
+    if ids.is_empty() {
+        println!("No ids provided.\n");
+        return Ok(());
+    }
+
+    // Ranges are resolved against the existing rows, so they can be as large as wanted.
+    let entries = store.get_entries_in_ranges(&ids)?;
+    let mut first_missing = None;
+    for range in &ids {
+        let found = entries
+            .iter()
+            .filter(|entry| range.contains(&entry.get_id()))
+            .count();
+        let requested = i128::from(*range.end()) - i128::from(*range.start()) + 1;
+        let missing = requested - found as i128;
+        if missing == 0 {
+            continue;
+        }
+        first_missing = first_missing.or(first_missing_id(range, &entries));
+        // A single id is already reported by the error returned at the end.
+        if ids.len() == 1 && range.start() == range.end() {
+            continue;
+        }
+        if range.start() == range.end() {
+            eprintln!("Entry with id {} {}.", range.start(), "not found".red());
+        } else {
+            eprintln!(
+                "{missing} ids of {} {}.",
+                readable_range(range),
+                "not found".red()
+            );
+        }
+    }
+    if entries.is_empty() {
+        return Err(TransferError::NotFound(
+            first_missing.unwrap_or(*ids[0].start()),
+        ));
+    }
+
+    let ids_list = ids
+        .iter()
+        .map(readable_range)
+        .collect::<Vec<_>>()
+        .join(", ");
+    if !assume_yes
+        && !ask_confirmation(&format!(
+            "Are you sure you want to delete the entries {ids_list}? (It will also delete from the cloud)"
+        ))?
+    {
+        return Err(TransferError::Aborted);
+    }
+
+    let mut deleted = 0;
+    let mut not_deleted = 0;
+    let mut reachable_servers = HashSet::new();
+    for entry in &entries {
+        let entry_id = entry.get_id();
+        let server = entry.get_server();
+        if !reachable_servers.contains(server) {
+            if let Err(err) = client.check_reachable(server).await {
+                eprintln!("Entry with id {entry_id} {}: {err}", "failed".red());
+                not_deleted += 1;
+                continue;
+            }
+            reachable_servers.insert(server.to_string());
+        }
+        match store.delete_entry(entry_id, client).await {
+            Ok(DeleteStatus::Deleted) => {
+                deleted += 1;
+                println!("Entry with id {entry_id} {}.", "deleted".green());
+            }
+            Ok(DeleteStatus::NotFound) => {
+                eprintln!("Entry with id {entry_id} {}.", "not found".red());
+                first_missing = first_missing.or(Some(entry_id));
+            }
+            Err(err) if assume_yes => {
+                eprintln!("Entry with id {entry_id} {}: {err}", "failed".red());
+                not_deleted += 1;
+            }
+            Err(err) => {
+                eprintln!("Entry with id {entry_id} {}: {err}", "failed".red());
+                if ask_confirmation("Do you want to delete the entry from the database anyway? (It will still be accessible from the link)")? {
+                    store.remove_entry(entry_id)?;
+                    deleted += 1;
+                    println!(
+                        "Entry with id {entry_id} {}.",
+                        "deleted from the local database only".yellow()
+                    );
+                } else {
+                    println!("Entry with id {entry_id} not deleted.");
+                    not_deleted += 1;
+                }
+            }
+        }
+    }
+    println!("\nDeleted {deleted} of {} entries.\n", entries.len());
+
+    if not_deleted > 0 {
+        return Err(format!(
+            "{not_deleted} of {} entries could not be deleted",
+            entries.len()
+        )
+        .into());
+    }
+    match first_missing {
+        Some(entry_id) => Err(TransferError::NotFound(entry_id)),
+        None => Ok(()),
+    }
+}
 
-static DATABASE: Lazy<Mutex<Database>> = Lazy::new(|| Mutex::new(Database::new().unwrap()));
+/// Lowest id of `range` without an entry, `entries` being ordered by id.
+fn first_missing_id(range: &RangeInclusive<i64>, entries: &[Link]) -> Option<i64> {
+    let mut expected = *range.start();
+    for entry_id in entries
+        .iter()
+        .map(Link::get_id)
+        .filter(|entry_id| range.contains(entry_id))
+    {
+        if entry_id != expected {
+            return Some(expected);
+        }
+        expected = entry_id.checked_add(1)?;
+    }
+    range.contains(&expected).then_some(expected)
+}
+
+/// Shows an id range like it is given in the command line, `3..7` or `3`.
+fn readable_range(range: &RangeInclusive<i64>) -> String {
+    if range.start() == range.end() {
+        range.start().to_string()
+    } else {
+        format!("{}..{}", range.start(), range.end())
+    }
+}
 
-async fn execute_delete_by_id() -> Result<(), TransferError> {
-    verify_transfer_connection().await;
+/// Deletes every entry matching `query` from the servers, concurrently, and then from the database.
+async fn execute_delete_matching(
+    query: &EntryQuery,
+    force: bool,
+    assume_yes: bool,
+    jobs: usize,
+    store: &mut Store,
+    client: &TransferClient,
+) -> Result<(), TransferError> {
+    let entries = store.get_entries(query)?;
+    if entries.is_empty() {
+        println!("\nNo entries match the given filters.\n");
+        return Ok(());
+    }
+
+    let count = entries.len();
     println!();
-    if utils::output_data(false, false)? == 0 {
-        println!("No data to delete");
-        exit(0);
+    terminal::output_links(&entries, &Columns::default())?;
+    if !assume_yes
+        && !ask_confirmation(&format!(
+            "Are you sure you want to delete these {count} entries? (It will also delete from the cloud)"
+        ))?
+    {
+        return Err(TransferError::Aborted);
+    }
+
+    let mut deleted = 0;
+    let mut failed = 0;
+    for (entry, result) in store.delete_entries(entries, client, jobs, force).await? {
+        let entry_id = entry.get_id();
+        match result {
+            Ok(()) => {
+                deleted += 1;
+                println!("Entry with id {entry_id} {}.", "deleted".green());
+            }
+            Err(err) if force => {
+                deleted += 1;
+                eprintln!(
+                    "Entry with id {entry_id} {}: {err}",
+                    "deleted from the local database only".yellow()
+                );
+            }
+            Err(err) => {
+                failed += 1;
+                eprintln!("Entry with id {entry_id} {}: {err}", "failed".red());
+            }
+        }
+    }
+    println!("\nDeleted {deleted} of {count} entries.\n");
+
+    if failed > 0 {
+        return Err(format!(
+            "{failed} entries could not be deleted from the server and were kept, use `--force` to remove them anyway"
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Shows the stored entries and reads the ids to delete, `None` if there is nothing to delete.
+fn ask_entry_ids(store: &Store) -> Result<Option<Vec<RangeInclusive<i64>>>, TransferError> {
+    println!();
+    if terminal::output_data(
+        store.get_all_entries()?,
+        &EntryQuery::default(),
+        &Columns::default(),
+    )? == 0
+    {
+        return Ok(None);
     }
     println!();
-    let mut id = String::new();
-    print!("Enter the id of the entry you want to remove: ");
+    let mut ids = String::new();
+    print!("Enter the ids of the entries you want to remove: ");
     io::stdout().flush()?;
-    io::stdin().read_line(&mut id)?;
+    io::stdin().read_line(&mut ids)?;
+
+    Ok(Some(
+        ids.split_whitespace()
+            .map(arg_parser::validate_id_range)
+            .collect::<Result<_, _>>()?,
+    ))
+}
 
-    let mut database = DATABASE.try_lock()?;
-    database.delete_entry(id.trim().parse::<i64>()?).await?;
+async fn execute_list(
+    columns: &Columns,
+    format: &OutputFormat,
+    query: &EntryQuery,
+    check_jobs: Option<usize>,
+    save: bool,
+    store: &Store,
+    client: &TransferClient,
+) -> Result<(), TransferError> {
+    let entries = list_entries(query, check_jobs, save, store, client).await?;
+    if !matches!(format, OutputFormat::Table) {
+        return terminal::output_serialized(entries, format);
+    }
+
+    println!();
+    terminal::output_data(entries, query, columns)?;
+    println!();
 
     Ok(())
 }
 
-fn execute_list(delete_links: bool, show_sha256: bool) -> Result<(), TransferError> {
+async fn execute_status(
+    query: &EntryQuery,
+    cached: bool,
+    jobs: usize,
+    save: bool,
+    store: &Store,
+    client: &TransferClient,
+) -> Result<(), TransferError> {
+    let check_jobs = (!cached).then_some(jobs);
+    let entries = list_entries(query, check_jobs, save, store, client).await?;
+
+    let mut summary: Vec<(String, usize)> = vec![];
+    for label in entries.iter().map(Link::server_status_label) {
+        match summary.iter_mut().find(|(existing, _)| *existing == label) {
+            Some((_, count)) => *count += 1,
+            None => summary.push((label, 1)),
+        }
+    }
+    let columns = Columns {
+        server_status: true,
+        ..Columns::default()
+    };
+
     println!();
-    utils::output_data(delete_links, show_sha256)?;
+    if terminal::output_data(entries, query, &columns)? > 0 {
+        let summary: Vec<String> = summary
+            .iter()
+            .map(|(label, count)| format!("{label}: {count}"))
+            .collect();
+        println!("\n{}", summary.join(", "));
+    }
     println!();
 
     Ok(())
 }
 
-fn execute_drop() -> Result<(), TransferError> {
-    let database = DATABASE.try_lock()?;
-    database.delete_database_file()?;
+/// Entries matching `query`, checked on their servers with `check_jobs` concurrent requests when given.
+async fn list_entries(
+    query: &EntryQuery,
+    check_jobs: Option<usize>,
+    save: bool,
+    store: &Store,
+    client: &TransferClient,
+) -> Result<Vec<Link>, TransferError> {
+    let entries = store.get_entries(query)?;
+    match check_jobs {
+        Some(jobs) => store.check_entries(entries, client, jobs, save).await,
+        None => Ok(entries),
+    }
+}
 
-    Ok(())
+/// Converts the filters of `list` to a query, dates are whole days in UTC.
+fn entry_query(
+    filters: ListFilters,
+    sort: SortBy,
+    reverse: bool,
+) -> Result<EntryQuery, TransferError> {
+    let start_of_day = |date: NaiveDate| -> Result<u64, TransferError> {
+        Ok(date.and_time(NaiveTime::MIN).timestamp().try_into()?)
+    };
+    let available = match (filters.available, filters.expired) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    };
+    let uploaded_before = filters
+        .until
+        .map(|date| start_of_day(date.succ_opt().ok_or("Invalid date")?))
+        .transpose()?;
+
+    Ok(EntryQuery {
+        name: name_pattern(filters.name, filters.regex)?,
+        available,
+        uploaded_since: filters.since.map(start_of_day).transpose()?,
+        uploaded_before,
+        sha256_prefix: filters.sha256_prefix,
+        limit: filters.limit,
+        offset: filters.offset,
+        sort,
+        reverse,
+    })
 }
 
-async fn execute_transfer_file<T>(path: T) -> Result<(), TransferError>
-where
-    T: AsRef<str>,
-{
-    match utils::get_file_size(path.as_ref()).await {
-        Ok(size) => {
-            println!("File size: {}", size.green());
+fn name_pattern(name: Option<String>, regex: bool) -> Result<Option<NamePattern>, TransferError> {
+    match name {
+        Some(pattern) if regex => {
+            Regex::new(&pattern).map_err(|err| format!("Invalid regex `{pattern}`: {err}"))?;
+            Ok(Some(NamePattern::Regex(pattern)))
         }
-        Err(err) => {
-            return Err(err);
+        Some(pattern) => Ok(Some(NamePattern::Glob(pattern))),
+        None => Ok(None),
+    }
+}
+
+async fn execute_download(
+    target: &str,
+    output: Option<PathBuf>,
+    force: bool,
+    decrypt: bool,
+    server_password: Option<String>,
+    store: &Store,
+    client: &TransferClient,
+) -> Result<(), TransferError> {
+    let entry = match target.parse::<i64>() {
+        Ok(entry_id) => Some(
+            store
+                .get_single_entry(entry_id)?
+                .ok_or(TransferError::NotFound(entry_id))?,
+        ),
+        Err(_) => store.get_entry_by_link(target)?,
+    };
+    let link = entry.as_ref().map_or(target, Link::get_link);
+    let expected_sha256sum = entry.as_ref().and_then(Link::get_sha256sum);
+    if expected_sha256sum.is_none() {
+        println!(
+            "{}",
+            "No sha256sum stored for this link, the download will not be verified.".yellow()
+        );
+    }
+
+    let decrypt = decrypt || entry.as_ref().is_some_and(Link::is_encrypted);
+
+    let output = match output {
+        Some(output) => output,
+        None => {
+            let file_name = link
+                .trim_end_matches('/')
+                .split('/')
+                .next_back()
+                .filter(|file_name| !file_name.is_empty())
+                .ok_or("Failed to get file name from the link, use `--output` to set it")?;
+            let file_name = if decrypt {
+                file_name
+                    .strip_suffix(ENCRYPTED_EXTENSION)
+                    .unwrap_or(file_name)
+            } else {
+                file_name
+            };
+            PathBuf::from(file_name)
+        }
+    };
+    if output.exists() && !force {
+        return Err(format!(
+            "Output file already exists: `{}`, use `--force` to overwrite it",
+            output.display()
+        )
+        .into());
+    }
+
+    println!(
+        "\nDownloading {} to {}\n",
+        link.green(),
+        output.display().green()
+    );
+    let server_password = match server_password {
+        Some(server_password) => Some(server_password),
+        None if entry.as_ref().is_some_and(Link::is_password_protected) => {
+            Some(rpassword::prompt_password("Enter the server password: ")?)
         }
+        None => None,
+    };
+    let passphrase = decrypt.then(|| ask_passphrase(false)).transpose()?;
+    let sha256sum = client
+        .download(
+            link,
+            &output,
+            expected_sha256sum,
+            passphrase,
+            server_password.as_deref(),
+        )
+        .await?;
+    println!("\n");
+    if expected_sha256sum.is_some() {
+        println!("Checksum verified: {}\n", sha256sum.green());
+    } else {
+        println!("SHA256: {}\n", sha256sum.green());
+    }
+
+    Ok(())
+}
+
+/// Reads the passphrase from the environment, falling back to prompting for it without echo.
+fn ask_passphrase(confirm: bool) -> Result<String, TransferError> {
+    if let Ok(passphrase) = env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+
+    let passphrase = rpassword::prompt_password("Enter the encryption passphrase: ")?;
+    if passphrase.is_empty() {
+        return Err("The passphrase cannot be empty".into());
+    }
+    if confirm && rpassword::prompt_password("Confirm the encryption passphrase: ")? != passphrase {
+        return Err("The passphrases do not match".into());
+    }
+
+    Ok(passphrase)
+}
+
+fn execute_drop(store: Store, backup_path: Option<PathBuf>) -> Result<(), TransferError> {
+    if !ask_confirmation("Are you sure you want to delete the database file?")? {
+        return Err(TransferError::Aborted);
+    }
+    if let Some(backup_path) = &backup_path {
+        store.backup_to(backup_path)?;
+        println!("Backup written to {}", backup_path.display().green());
+    }
+    store.delete_database_file()?;
+    println!("Database file deleted.\n");
+    if let Some(file_name) = backup_path.as_deref().and_then(Path::file_name) {
+        println!(
+            "Run `transferhelper restore {}` to bring it back.\n",
+            file_name.to_string_lossy()
+        );
+    }
+
+    Ok(())
+}
+
+/// Timestamped path inside the config folder where `drop` and `restore` back up the database.
+fn backup_path(database_file: &str) -> Result<PathBuf, TransferError> {
+    let timestamp = Utc::now().format("%Y%m%d-%H%M%S");
+    Ok(config_app_folder()?.join(format!("{database_file}.{timestamp}.bak")))
+}
+
+fn execute_restore(
+    backup: PathBuf,
+    assume_yes: bool,
+    store: Store,
+    backup_path: PathBuf,
+) -> Result<(), TransferError> {
+    let backup = if backup.exists() {
+        backup
+    } else {
+        config_app_folder()?.join(backup)
     };
+    let backup_entries = Store::validate_backup(&backup)?;
+
+    let current_entries = store.get_all_entries()?.len();
+    if current_entries > 0
+        && !assume_yes
+        && !ask_confirmation(&format!(
+            "The database has {current_entries} entries, are you sure you want to replace it with the backup of {backup_entries} entries?"
+        ))?
+    {
+        return Err(TransferError::Aborted);
+    }
+    if current_entries > 0 {
+        store.backup_to(&backup_path)?;
+        println!(
+            "Backup of the current database written to {}",
+            backup_path.display().green()
+        );
+    }
+
+    store.restore(&backup)?;
+    println!(
+        "\nRestored {backup_entries} entries from {}\n",
+        backup.display().green()
+    );
+    if let Some(file_name) = backup_path.file_name().filter(|_| current_entries > 0) {
+        println!(
+            "Run `transferhelper restore {}` to undo it.\n",
+            file_name.to_string_lossy()
+        );
+    }
 
-    verify_transfer_connection().await;
+    Ok(())
+}
 
+async fn execute_prune(
+    verify: bool,
+    dry_run: bool,
+    assume_yes: bool,
+    jobs: usize,
+    store: &mut Store,
+    client: &TransferClient,
+) -> Result<(), TransferError> {
+    let mut expired = store.get_entries(&EntryQuery {
+        available: Some(false),
+        ..EntryQuery::default()
+    })?;
+    if expired.is_empty() {
+        println!("\nNo expired entries to prune.\n");
+        return Ok(());
+    }
+    if verify {
+        expired = removed_from_server(expired, jobs, client).await;
+        if expired.is_empty() {
+            println!("\nNone of the expired entries has been removed from the server yet.\n");
+            return Ok(());
+        }
+    }
+
+    let ids: Vec<i64> = expired.iter().map(Link::get_id).collect();
+    println!();
+    terminal::output_links(&expired, &Columns::default())?;
+    if dry_run {
+        println!("\n{} expired entries would be pruned.\n", ids.len());
+        return Ok(());
+    }
+    if !assume_yes
+        && !ask_confirmation(&format!(
+            "Are you sure you want to remove {} expired entries from the database?",
+            ids.len()
+        ))?
     {
-        let default_name = Path::new(path.as_ref())
-            .file_name()
-            .ok_or(io::Error::new(
-                io::ErrorKind::Other,
-                "Failed to get file name",
-            ))?
-            .to_str()
-            .unwrap_or("default-name");
-        let mut entry_name = String::new();
-        print!(
-            "\nEnter the name of the entry (Default name: {}): ",
-            default_name.green()
+        return Err(TransferError::Aborted);
+    }
+
+    let pruned = store.remove_entries(&ids)?;
+    println!("Pruned {pruned} expired entries.\n");
+
+    Ok(())
+}
+
+/// Keeps the entries whose link the server no longer has, reporting the ones that are kept.
+async fn removed_from_server(
+    entries: Vec<Link>,
+    jobs: usize,
+    client: &TransferClient,
+) -> Vec<Link> {
+    let results: Vec<_> = stream::iter(entries)
+        .map(|entry| async move {
+            let status = client.link_status(entry.get_link()).await;
+            (entry, status)
+        })
+        .buffer_unordered(jobs)
+        .collect()
+        .await;
+
+    let mut removed = vec![];
+    for (entry, status) in results {
+        let kept = format!("Keeping {} ({}):", entry.get_id(), entry.display_name());
+        match status {
+            Ok(StatusCode::NOT_FOUND | StatusCode::GONE) => removed.push(entry),
+            Ok(StatusCode::OK) => eprintln!("{kept} {}", "still available on the server".yellow()),
+            Ok(code) => eprintln!("{kept} {}", format!("the server answered {code}").yellow()),
+            Err(err) => eprintln!("{kept} {}", err.red()),
+        }
+    }
+    removed.sort_unstable_by_key(Link::get_id);
+    removed
+}
+
+fn execute_migrate(dry_run: bool, store: &mut Store) -> Result<(), TransferError> {
+    let migrations = if dry_run {
+        store.pending_migrations()?
+    } else {
+        store.migrate()?
+    };
+    if migrations.is_empty() {
+        println!(
+            "The database is up to date, schema version {}.",
+            store.schema_version()?
         );
-        io::stdout().flush()?;
-        io::stdin().read_line(&mut entry_name)?;
-        if entry_name.trim().is_empty() {
-            entry_name = default_name.to_string();
+        return Ok(());
+    }
+
+    let verb = if dry_run { "Pending" } else { "Applied" };
+    for (version, description) in migrations {
+        println!("{verb} migration {version}: {description}");
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn execute_transfer_files(
+    paths: &[String],
+    compression_level: Option<&CompressionLevel>,
+    name: Option<&str>,
+    assume_yes: bool,
+    jobs: usize,
+    store: &Store,
+    client: &TransferClient,
+    options: &UploadOptions,
+) -> Result<(), TransferError> {
+    let mut files = terminal::expand_paths(paths)?;
+    if files.is_empty() {
+        return Err("No files matched the provided paths".into());
+    }
+    if name.is_some() && files.len() > 1 {
+        return Err("`--name` can only be used when uploading a single file".into());
+    }
+    let reads_stdin = files.iter().any(|file| file == STDIN_PATH);
+    if reads_stdin && compression_level.is_some() {
+        return Err("Compression is not supported when reading from stdin".into());
+    }
+    // The paths given by the user, recorded as the original path of the compressed uploads.
+    let sources = files.clone();
+    if let Some(compression_level) = compression_level {
+        files = files
+            .iter()
+            .map(|path| compress_path(path, compression_level))
+            .collect::<Result<_, _>>()?;
+    }
+
+    for file in &files {
+        if file == STDIN_PATH {
+            println!("Reading from {}", "stdin".green());
+            continue;
         }
+        let size = get_file_size(file).await?;
+        println!("File size of {}: {}", file, size.green());
+    }
+
+    client
+        .check_reachable(client.config().get_server_url())
+        .await?;
+
+    let entries = if let ([file], [source]) = (files.as_slice(), sources.as_slice()) {
+        vec![(ask_entry_name(file, name, assume_yes)?, file, source)]
+    } else {
         println!();
-        let database = DATABASE.try_lock()?;
-        database
-            .transfer_file(entry_name.trim(), path.as_ref())
-            .await?;
+        files
+            .iter()
+            .zip(&sources)
+            .map(|(file, source)| Ok((default_entry_name(file)?, file, source)))
+            .collect::<Result<Vec<_>, TransferError>>()?
+    };
+
+    let progress = Arc::new(TransferProgress::for_files(&files).await?);
+    let results: Vec<_> = stream::iter(&entries)
+        .map(|(entry_name, file, source)| {
+            let progress = Arc::clone(&progress);
+            async move {
+                let result = store
+                    .transfer_file(entry_name, file, source, client, options, progress)
+                    .await;
+                (file, result)
+            }
+        })
+        .buffer_unordered(jobs)
+        .collect()
+        .await;
+    println!("\n");
+
+    let mut uploaded_ids = vec![];
+    for (file, result) in results {
+        match result {
+            Ok(entry_id) => uploaded_ids.push(entry_id),
+            Err(err) => eprintln!("Failed to upload {}: {}", file, err.red()),
+        }
     }
+    uploaded_ids.sort_unstable();
+    let uploaded = store.get_entries_by_ids(&uploaded_ids)?;
 
-    utils::output_data(false, true)?;
-    println!();
+    let uploaded_count = uploaded.len();
+    if uploaded_count > 0 {
+        terminal::output_links(
+            &uploaded,
+            &Columns {
+                sha256: true,
+                ..Columns::default()
+            },
+        )?;
+        println!();
+    }
+    if uploaded_count < entries.len() {
+        return Err(format!("Uploaded {uploaded_count} of {} files", entries.len()).into());
+    }
 
     Ok(())
 }
 
-async fn execute_transfer_compressed<T>(
-    path: T,
+fn compress_path(
+    path: &str,
     compression_level: &CompressionLevel,
-) -> Result<(), TransferError>
-where
-    T: AsRef<str>,
-{
-    let compressed_path = format!("{}.tar.gz", path.as_ref());
-    let compressor = Compressor::new(path.as_ref(), &compressed_path);
+) -> Result<String, TransferError> {
+    let compressed_path = format!("{path}.tar.gz");
+    let compressor = Compressor::new(path, &compressed_path);
     println!(
         "Compressing {} with compression level {}...\n",
-        path.as_ref().green(),
+        path.green(),
         u32::from(compression_level).green()
     );
     let compress_info = compressor.compress(compression_level)?;
 
+    println!("Compressed {} to {}", path.green(), compressed_path.green());
     println!(
-        "Compressed {} to {}",
-        path.as_ref().green(),
-        compressed_path.green()
-    );
-    println!(
-        "Compression ratio: {}",
+        "Compression ratio: {}\n",
         compress_info.ratio_formatted(2).green()
     );
 
-    match utils::get_file_size(&compressed_path).await {
-        Ok(size) => {
-            println!("File size: {}", size.green());
-        }
-        Err(err) => {
-            eprintln!("{err}");
-            exit(1);
-        }
-    };
-
-    verify_transfer_connection().await;
+    Ok(compressed_path)
+}
 
-    {
-        let default_name = Path::new(&compressed_path)
-            .file_name()
-            .ok_or(io::Error::new(
-                io::ErrorKind::Other,
-                "Failed to get file name",
-            ))?
-            .to_str()
-            .unwrap_or("default-name");
-        let mut entry_name = String::new();
-        print!(
-            "\nEnter the name of the entry (Default name: {}): ",
-            default_name.green()
-        );
-        io::stdout().flush()?;
-        io::stdin().read_line(&mut entry_name)?;
-        if entry_name.trim().is_empty() {
-            entry_name = default_name.to_string();
-        }
-        println!();
-        let database = DATABASE.try_lock()?;
-        database
-            .transfer_file(entry_name.trim(), &compressed_path)
-            .await?;
+fn default_entry_name(path: &str) -> Result<String, TransferError> {
+    if path == STDIN_PATH {
+        return Ok(String::from("stdin"));
     }
 
-    utils::output_data(false, true)?;
-    println!();
-
-    Ok(())
+    Ok(Path::new(path)
+        .file_name()
+        .ok_or(io::Error::other("Failed to get file name"))?
+        .to_str()
+        .unwrap_or("default-name")
+        .to_string())
 }
 
-async fn verify_transfer_connection() {
-    match transfer_response_code().await {
-        Ok(StatusCode::OK) => {}
-        Ok(code) => {
-            eprintln!("Transfer.sh is not reachable, status code: {}", code.red());
-            exit(1);
-        }
-        Err(err) => {
-            eprintln!("Transfer.sh is not reachable: {}", err.red());
-            exit(1);
-        }
+/// Resolves the entry name, only prompting for it when stdin is interactive and `--yes` was not given.
+fn ask_entry_name(
+    path: &str,
+    name: Option<&str>,
+    assume_yes: bool,
+) -> Result<String, TransferError> {
+    if let Some(name) = name {
+        return Ok(name.to_string());
+    }
+
+    let default_name = default_entry_name(path)?;
+    if assume_yes || path == STDIN_PATH || !io::stdin().is_terminal() {
+        println!("\nEntry name: {}\n", default_name.green());
+        return Ok(default_name);
+    }
+
+    let mut entry_name = String::new();
+    print!(
+        "\nEnter the name of the entry (Default name: {}): ",
+        default_name.green()
+    );
+    io::stdout().flush()?;
+    io::stdin().read_line(&mut entry_name)?;
+    println!();
+    if entry_name.trim().is_empty() {
+        return Ok(default_name);
     }
+
+    Ok(entry_name.trim().to_string())
 }
 
 async fn run_app(args: AppArguments) -> Result<(), TransferError> {
-    {
-        let database = DATABASE.try_lock()?;
-        database.create_table()?;
+    create_config_app_folder()?;
+    let mut config = get_config()?;
+    let database_path = config_app_folder()?.join(config.get_database_file());
+    // `db migrate` has to see the database before it is migrated, any other command migrates it.
+    let store = if matches!(args.app_subcommands, Some(AppOptions::Db { .. })) {
+        Store::open_without_migrating(database_path)
+    } else {
+        Store::open(database_path)
+    };
+    let mut store = store.map_err(|err| format!("Failed to open the database: {err}"))?;
+    if let Some(server) = args.server {
+        config.override_server_url(server);
     }
+    if args.insecure {
+        config.set_insecure(true);
+    }
+    let mut client = TransferClient::new(config)?;
+    client.set_reporter(Arc::new(TerminalReporter::default()));
     let Some(subcommands) = args.app_subcommands else {
-        execute_list(false, false)?;
-        exit(0);
+        let query = EntryQuery {
+            sort: client.config().get_sort(),
+            reverse: client.config().is_sort_reverse(),
+            ..EntryQuery::default()
+        };
+        return execute_list(
+            &Columns::default(),
+            &OutputFormat::Table,
+            &query,
+            None,
+            false,
+            &store,
+            &client,
+        )
+        .await;
     };
 
     match subcommands {
         AppOptions::List {
             delete_link,
             sha256,
-        } => execute_list(delete_link, sha256)?,
-        AppOptions::Delete => execute_delete_by_id().await?,
-        AppOptions::Drop => execute_drop()?,
+            details,
+            format,
+            filters,
+            sort,
+            reverse,
+            check,
+            save,
+            jobs,
+        } => {
+            let config = client.config();
+            // `--reverse` flips the configured order, which may already be reversed.
+            let query = entry_query(
+                filters,
+                sort.map_or(config.get_sort(), SortBy::from),
+                reverse != config.is_sort_reverse(),
+            )?;
+            let columns = Columns {
+                delete_link,
+                sha256,
+                details,
+                server_status: check,
+            };
+            let check_jobs = check.then_some(jobs.into());
+            execute_list(&columns, &format, &query, check_jobs, save, &store, &client).await?;
+        }
+        AppOptions::Status {
+            filters,
+            cached,
+            save,
+            jobs,
+        } => {
+            let config = client.config();
+            let query = entry_query(filters, config.get_sort(), config.is_sort_reverse())?;
+            execute_status(&query, cached, jobs.into(), save, &store, &client).await?;
+        }
+        AppOptions::Delete {
+            ids,
+            yes,
+            all,
+            expired,
+            name,
+            regex,
+            force,
+            jobs,
+        } => {
+            if all || expired || name.is_some() {
+                let query = EntryQuery {
+                    name: name_pattern(name, regex)?,
+                    available: expired.then_some(false),
+                    ..EntryQuery::default()
+                };
+                execute_delete_matching(&query, force, yes, jobs.into(), &mut store, &client)
+                    .await?;
+            } else {
+                execute_delete_by_id(ids, yes, &mut store, &client).await?;
+            }
+        }
+        AppOptions::Download {
+            target,
+            output,
+            force,
+            decrypt,
+            server_password,
+        } => {
+            execute_download(
+                &target,
+                output,
+                force,
+                decrypt,
+                server_password,
+                &store,
+                &client,
+            )
+            .await?;
+        }
+        AppOptions::Drop { no_backup } => {
+            let backup_path = if no_backup {
+                None
+            } else {
+                Some(backup_path(client.config().get_database_file())?)
+            };
+            execute_drop(store, backup_path)?;
+        }
+        AppOptions::Restore { backup, yes } => {
+            let backup_path = backup_path(client.config().get_database_file())?;
+            execute_restore(backup, yes, store, backup_path)?;
+        }
+        AppOptions::Prune {
+            verify,
+            dry_run,
+            yes,
+            jobs,
+        } => execute_prune(verify, dry_run, yes, jobs.into(), &mut store, &client).await?,
+        AppOptions::Db {
+            command: DbOptions::Migrate { dry_run },
+        } => execute_migrate(dry_run, &mut store)?,
         AppOptions::Upload {
-            path,
+            paths,
             compress,
             level,
+            max_days,
+            max_downloads,
+            name,
+            yes,
+            jobs,
+            encrypt,
+            server_password,
         } => {
-            if compress {
-                execute_transfer_compressed(path, &level).await?;
-            } else {
-                execute_transfer_file(path).await?;
-            }
+            let options = UploadOptions {
+                max_days,
+                max_downloads,
+                encryption_passphrase: encrypt.then(|| ask_passphrase(true)).transpose()?,
+                server_password,
+            };
+            execute_transfer_files(
+                &paths,
+                compress.then_some(&level),
+                name.as_deref(),
+                yes,
+                usize::from(jobs),
+                &store,
+                &client,
+                &options,
+            )
+            .await?;
         }
     }
 
@@ -221,10 +989,14 @@ async fn run_app(args: AppArguments) -> Result<(), TransferError> {
 }
 
 #[tokio::main(flavor = "current_thread")]
-async fn main() -> Result<(), TransferError> {
+async fn main() -> ExitCode {
     let args = arg_parser::AppArguments::parse();
 
-    run_app(args).await?;
-
-    Ok(())
+    match run_app(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err.red());
+            ExitCode::from(err.exit_code())
+        }
+    }
 }
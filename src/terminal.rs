@@ -0,0 +1,153 @@
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use chrono::{
+    prelude::{NaiveDateTime, Utc},
+    TimeZone,
+};
+use owo_colors::OwoColorize;
+use transferhelper::{
+    readable_size, EntryQuery, Link, TransferError, TransferProgress, TransferReporter, STDIN_PATH,
+};
+
+use crate::{arg_parser::OutputFormat, transfer_table};
+
+pub fn ask_confirmation(text: &str) -> Result<bool, io::Error> {
+    let mut confirmation = String::new();
+    print!("\n{} (y/N): ", text.yellow());
+    io::stdout().flush()?;
+    io::stdin().read_line(&mut confirmation)?;
+    println!();
+    Ok(confirmation.trim().to_lowercase().starts_with('y'))
+}
+
+/// Shows the progress of the transfers on a single line and the retries below it.
+#[derive(Default)]
+pub struct TerminalReporter {
+    /// Whether the cursor is at the end of the progress line.
+    on_progress_line: AtomicBool,
+}
+
+impl TransferReporter for TerminalReporter {
+    fn progress(&self, progress: &TransferProgress) {
+        let label = if progress.get_files() == 1 {
+            format!("{}...", progress.get_action())
+        } else {
+            format!(
+                "{} {} files...",
+                progress.get_action(),
+                progress.get_files()
+            )
+        };
+        let transferred = progress.get_transferred();
+        match progress.get_total_size() {
+            Some(total_size) => {
+                let percentage = (transferred as f64 / total_size as f64) * 100.0;
+                print!("\r{label} {:.2}%", percentage.green());
+            }
+            None => print!("\r{label} {}   ", readable_size(transferred).green()),
+        }
+        let _ = io::stdout().flush();
+        self.on_progress_line.store(true, Ordering::Relaxed);
+    }
+
+    fn retry(&self, message: &str) {
+        if self.on_progress_line.swap(false, Ordering::Relaxed) {
+            println!();
+        }
+        eprintln!("{}", message.yellow());
+    }
+}
+
+/// Optional columns of the entries table.
+#[derive(Default)]
+pub struct Columns {
+    /// Shows the delete link instead of the link.
+    pub delete_link: bool,
+    pub sha256: bool,
+    /// Size, content type and original path.
+    pub details: bool,
+    pub server_status: bool,
+}
+
+/// Shows the entries returned for `query`, or why there are none.
+pub fn output_data(
+    data: Vec<Link>,
+    query: &EntryQuery,
+    columns: &Columns,
+) -> Result<usize, TransferError> {
+    if data.is_empty() && query.is_filtered() {
+        println!("No entries match the given filters.");
+        return Ok(0);
+    }
+    if data.is_empty() {
+        println!("No entries found.");
+        println!("Run `transferhelper -h` to see all available commands.\n");
+        return Ok(0);
+    }
+    output_links(&data, columns)
+}
+
+pub fn output_links(data: &[Link], columns: &Columns) -> Result<usize, TransferError> {
+    let data_len = data.len();
+    transfer_table!(data, columns);
+
+    Ok(data_len)
+}
+
+/// Expands glob patterns that do not match an existing path, keeping the given order.
+pub fn expand_paths(paths: &[String]) -> Result<Vec<String>, TransferError> {
+    let mut seen = HashSet::new();
+    let mut expanded = vec![];
+    for path in paths {
+        if path == STDIN_PATH || Path::new(path).exists() {
+            expanded.push(path.clone());
+            continue;
+        }
+        for entry in glob::glob(path).map_err(|err| err.to_string())? {
+            let entry = entry.map_err(|err| err.to_string())?;
+            expanded.push(entry.to_string_lossy().into_owned());
+        }
+    }
+    expanded.retain(|path| seen.insert(path.clone()));
+
+    Ok(expanded)
+}
+
+pub fn output_serialized(data: Vec<Link>, format: &OutputFormat) -> Result<(), TransferError> {
+    let delimiter = match format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&data)?);
+            return Ok(());
+        }
+        OutputFormat::Table => return Err("Table output is not a serialized format".into()),
+        OutputFormat::Csv => b',',
+        OutputFormat::Tsv => b'\t',
+    };
+
+    // The header row is only written along with the first record, so an
+    // empty list would print nothing at all.
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .from_writer(io::stdout());
+    writer.write_record(Link::FIELDS)?;
+    for entry in data {
+        writer.serialize(entry)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn readable_date(expire_time: u64) -> Result<String, TransferError> {
+    let date = TimeZone::from_utc_datetime(
+        &Utc,
+        &NaiveDateTime::from_timestamp_opt(expire_time.try_into()?, 0).ok_or("Invalid date")?,
+    );
+    Ok(date.format("%d-%m-%Y").to_string())
+}
@@ -1,81 +1,493 @@
-use chrono::{
-    prelude::{NaiveDateTime, Utc},
-    TimeZone,
-};
-use dirs::config_dir;
-use owo_colors::OwoColorize;
-use reqwest::{Response, StatusCode};
+use dirs::home_dir;
+use reqwest::StatusCode;
 use rusqlite::Row;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
 use std::{
-    fs::{create_dir_all, read_to_string, write},
-    io::{self, Write},
-    path::PathBuf,
-    process::exit,
-    sync::Arc,
-    time::{SystemTime, SystemTimeError, UNIX_EPOCH},
+    env,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, SystemTimeError, UNIX_EPOCH},
 };
-use tokio::io::{AsyncReadExt, BufReader};
-use tokio_stream::StreamExt;
-use tokio_util::io::ReaderStream;
 
-use crate::{errors::TransferError, transfer_table, DATABASE};
-const UNIX_WEEK: u64 = 1_209_600;
+use crate::{database::SortBy, errors::TransferError};
+
+pub(crate) const UNIX_WEEK: u64 = 1_209_600;
+pub(crate) const UNIX_DAY: u64 = 86_400;
+pub const DEFAULT_SERVER_URL: &str = "https://transfer.sh/";
+/// Path used to upload whatever is piped into stdin.
+pub const STDIN_PATH: &str = "-";
+/// Appended to the name of the files encrypted before uploading.
+pub const ENCRYPTED_EXTENSION: &str = ".enc";
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_RETRY_DELAY: u64 = 1;
+const USERNAME_ENV: &str = "TRANSFERHELPER_USERNAME";
+const PASSWORD_ENV: &str = "TRANSFERHELPER_PASSWORD";
+
+#[derive(Clone, Default)]
+pub struct UploadOptions {
+    pub max_days: Option<u32>,
+    pub max_downloads: Option<u32>,
+    pub encryption_passphrase: Option<String>,
+    pub server_password: Option<String>,
+}
+
+/// Progress of the transfers of a single invocation, shared by every file being uploaded.
+pub struct TransferProgress {
+    action: &'static str,
+    files: usize,
+    /// Unknown when reading from stdin, in which case only the transferred size is shown.
+    total_size: Option<u64>,
+    transferred: AtomicU64,
+}
+
+impl TransferProgress {
+    pub async fn for_files(files: &[String]) -> Result<TransferProgress, TransferError> {
+        let mut total_size = Some(0);
+        for file in files {
+            if file == STDIN_PATH {
+                total_size = None;
+            } else if let Some(size) = &mut total_size {
+                *size += tokio::fs::metadata(file).await?.len();
+            }
+        }
+
+        Ok(TransferProgress {
+            action: "Uploading",
+            files: files.len(),
+            total_size,
+            transferred: AtomicU64::new(0),
+        })
+    }
+
+    pub fn for_download(total_size: Option<u64>) -> TransferProgress {
+        TransferProgress {
+            action: "Downloading",
+            files: 1,
+            total_size,
+            transferred: AtomicU64::new(0),
+        }
+    }
+
+    /// "Uploading" or "Downloading".
+    pub fn get_action(&self) -> &str {
+        self.action
+    }
+
+    pub fn get_files(&self) -> usize {
+        self.files
+    }
+
+    pub fn get_total_size(&self) -> Option<u64> {
+        self.total_size
+    }
+
+    pub fn get_transferred(&self) -> u64 {
+        self.transferred.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn advance(&self, bytes: u64) {
+        self.transferred.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Removes the bytes of a failed attempt that is about to be retried.
+    pub(crate) fn rewind(&self, bytes: u64) {
+        self.transferred.fetch_sub(bytes, Ordering::Relaxed);
+    }
+}
+
+/// Receives the progress of the transfers and the retries of failed requests, the library
+/// never writes to the terminal itself. Both methods do nothing by default.
+pub trait TransferReporter: Send + Sync {
+    /// Called every time a chunk is sent or received.
+    fn progress(&self, _progress: &TransferProgress) {}
+
+    /// Called before a failed request is sent again, `message` explains why and when.
+    fn retry(&self, _message: &str) {}
+}
 
 pub struct TransferResponse {
     pub transfer_link: String,
     pub delete_link: String,
+    pub sha256sum: String,
+    /// Size in bytes of the file before being encrypted.
+    pub size: u64,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Config {
     #[serde(rename = "database_file")]
     database_file: String,
+    #[serde(rename = "server_url", default = "default_server_url")]
+    server_url: String,
+    #[serde(rename = "username", default, skip_serializing_if = "Option::is_none")]
+    username: Option<String>,
+    #[serde(rename = "password", default, skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+    #[serde(rename = "proxy_url", default, skip_serializing_if = "Option::is_none")]
+    proxy_url: Option<String>,
+    /// Seconds to wait for the connection to the server.
+    #[serde(
+        rename = "connect_timeout",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    connect_timeout: Option<u64>,
+    /// Seconds to wait for data from the server before giving up, uploads only wait once the whole body was sent.
+    #[serde(
+        rename = "read_timeout",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    read_timeout: Option<u64>,
+    /// PEM file with the certificates trusted in addition to the system ones.
+    #[serde(rename = "ca_bundle", default, skip_serializing_if = "Option::is_none")]
+    ca_bundle: Option<PathBuf>,
+    #[serde(rename = "insecure", default, skip_serializing_if = "is_false")]
+    insecure: bool,
+    /// Times a failed upload or delete is sent again, only for transient errors.
+    #[serde(
+        rename = "max_retries",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    max_retries: Option<u32>,
+    /// Seconds waited before the first retry, doubled on every following one.
+    #[serde(
+        rename = "retry_delay",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    retry_delay: Option<u64>,
+    /// Order of the listed entries when `--sort` is not given.
+    #[serde(rename = "sort", default, skip_serializing_if = "Option::is_none")]
+    sort: Option<SortBy>,
+    #[serde(rename = "sort_reverse", default, skip_serializing_if = "is_false")]
+    sort_reverse: bool,
+    /// Server the `username` and `password` belong to when `server_url` was overridden.
+    #[serde(skip)]
+    credentials_server_url: Option<String>,
 }
 
-impl Config {
-    fn new() -> Config {
+impl Default for Config {
+    fn default() -> Config {
         Config {
             database_file: String::from("transfer-sh-helper.db"),
+            server_url: default_server_url(),
+            username: None,
+            password: None,
+            proxy_url: None,
+            connect_timeout: None,
+            read_timeout: None,
+            ca_bundle: None,
+            insecure: false,
+            max_retries: None,
+            retry_delay: None,
+            sort: None,
+            sort_reverse: false,
+            credentials_server_url: None,
         }
     }
+}
 
+impl Config {
     pub fn get_database_file(&self) -> &str {
         &self.database_file
     }
+
+    pub fn get_server_url(&self) -> &str {
+        &self.server_url
+    }
+
+    pub fn set_server_url(&mut self, server_url: String) {
+        self.server_url = server_url;
+    }
+
+    /// Sends the requests to `server_url` while the credentials stay bound to the server
+    /// configured so far, as done by the `--server` flag.
+    pub fn override_server_url(&mut self, server_url: String) {
+        let configured = std::mem::replace(&mut self.server_url, server_url);
+        self.credentials_server_url.get_or_insert(configured);
+    }
+
+    /// Basic auth credentials sent to the origin of `server_url`, see [`Config::credentials_for`].
+    pub fn set_credentials(&mut self, username: String, password: Option<String>) {
+        self.username = Some(username);
+        self.password = password;
+    }
+
+    pub fn get_proxy_url(&self) -> Option<&str> {
+        self.proxy_url.as_deref()
+    }
+
+    pub fn set_proxy_url(&mut self, proxy_url: Option<String>) {
+        self.proxy_url = proxy_url;
+    }
+
+    pub fn get_connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout.map(Duration::from_secs)
+    }
+
+    /// Seconds to wait for the connection to the server, `None` waits as long as the system does.
+    pub fn set_connect_timeout(&mut self, seconds: Option<u64>) {
+        self.connect_timeout = seconds;
+    }
+
+    pub fn get_read_timeout(&self) -> Option<Duration> {
+        self.read_timeout.map(Duration::from_secs)
+    }
+
+    /// Seconds to wait for data from the server, `None` waits forever.
+    pub fn set_read_timeout(&mut self, seconds: Option<u64>) {
+        self.read_timeout = seconds;
+    }
+
+    pub fn get_ca_bundle(&self) -> Option<&Path> {
+        self.ca_bundle.as_deref()
+    }
+
+    pub fn set_ca_bundle(&mut self, ca_bundle: Option<PathBuf>) {
+        self.ca_bundle = ca_bundle;
+    }
+
+    pub fn is_insecure(&self) -> bool {
+        self.insecure
+    }
+
+    pub fn set_insecure(&mut self, insecure: bool) {
+        self.insecure = insecure;
+    }
+
+    pub fn get_max_retries(&self) -> u32 {
+        self.max_retries.unwrap_or(DEFAULT_MAX_RETRIES)
+    }
+
+    pub fn set_max_retries(&mut self, max_retries: u32) {
+        self.max_retries = Some(max_retries);
+    }
+
+    pub fn get_retry_delay(&self) -> Duration {
+        Duration::from_secs(self.retry_delay.unwrap_or(DEFAULT_RETRY_DELAY))
+    }
+
+    /// Seconds to wait before the first retry.
+    pub fn set_retry_delay(&mut self, seconds: u64) {
+        self.retry_delay = Some(seconds);
+    }
+
+    pub fn get_sort(&self) -> SortBy {
+        self.sort.unwrap_or_default()
+    }
+
+    pub fn set_sort(&mut self, sort: SortBy) {
+        self.sort = Some(sort);
+    }
+
+    pub fn is_sort_reverse(&self) -> bool {
+        self.sort_reverse
+    }
+
+    pub fn set_sort_reverse(&mut self, sort_reverse: bool) {
+        self.sort_reverse = sort_reverse;
+    }
+
+    /// Basic auth credentials used for `url`, taken from the environment, the config file or the netrc file, in that order.
+    /// The environment and the config file only apply to the origin of the configured server,
+    /// which an overridden `server_url` does not change.
+    pub fn credentials_for(&self, url: &str) -> Option<(String, Option<String>)> {
+        let url = reqwest::Url::parse(url).ok()?;
+        let credentials_server_url = self
+            .credentials_server_url
+            .as_deref()
+            .unwrap_or(&self.server_url);
+        let is_configured_server = reqwest::Url::parse(credentials_server_url)
+            .is_ok_and(|server_url| server_url.origin() == url.origin());
+        if is_configured_server {
+            if let Ok(username) = env::var(USERNAME_ENV) {
+                return Some((username, env::var(PASSWORD_ENV).ok()));
+            }
+            if let Some(username) = &self.username {
+                return Some((username.clone(), self.password.clone()));
+            }
+        }
+        netrc_credentials(url.host_str()?)
+    }
+}
+
+fn default_server_url() -> String {
+    String::from(DEFAULT_SERVER_URL)
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
 }
 
+#[derive(Serialize)]
 pub struct Link {
     id: i64,
     name: String,
     link: String,
     delete_link: String,
-    unix_time: u64,
+    upload_time: u64,
+    expire_time: u64,
     is_available: bool,
+    #[serde(rename = "sha256")]
     sha256sum: Option<String>,
+    server: String,
+    encrypted: bool,
+    password_protected: bool,
+    size: Option<u64>,
+    content_type: Option<String>,
+    original_path: Option<String>,
+    /// Status code of the last check of the link, empty if the server was unreachable.
+    server_status: Option<u16>,
+    /// Unix time of the last check of the link, empty if it was never checked.
+    checked_time: Option<u64>,
 }
 
 impl Link {
-    pub fn new(row: &Row) -> Result<Link, TransferError> {
+    /// Serialized field names, in the order they are written.
+    pub const FIELDS: [&'static str; 16] = [
+        "id",
+        "name",
+        "link",
+        "delete_link",
+        "upload_time",
+        "expire_time",
+        "is_available",
+        "sha256",
+        "server",
+        "encrypted",
+        "password_protected",
+        "size",
+        "content_type",
+        "original_path",
+        "server_status",
+        "checked_time",
+    ];
+
+    pub(crate) fn new(row: &Row) -> Result<Link, TransferError> {
+        let unix_time: u64 = row.get(4)?;
+        let max_days: Option<u32> = row.get(7)?;
+        let expire_time = unix_time + Link::retention_secs(max_days);
         Ok(Link {
             id: row.get(0)?,
             name: row.get(1)?,
             link: row.get(2)?,
             delete_link: row.get(3)?,
-            unix_time: row.get(4)?,
-            is_available: Link::is_link_available(row.get(4)?)?,
+            upload_time: unix_time,
+            expire_time,
+            is_available: Link::is_link_available(expire_time)?,
             sha256sum: row.get(5)?,
+            server: row.get(6)?,
+            encrypted: row.get(8)?,
+            password_protected: row.get(9)?,
+            size: row.get(10)?,
+            content_type: row.get(11)?,
+            original_path: row.get(12)?,
+            server_status: row.get(13)?,
+            checked_time: row.get(14)?,
         })
     }
 
-    fn is_link_available(upload_time: u64) -> Result<bool, SystemTimeError> {
-        Ok(current_time()? - upload_time < UNIX_WEEK)
+    /// Entries uploaded without `Max-Days` follow the server default retention.
+    fn retention_secs(max_days: Option<u32>) -> u64 {
+        max_days.map_or(UNIX_WEEK, |days| u64::from(days) * UNIX_DAY)
+    }
+
+    fn is_link_available(expire_time: u64) -> Result<bool, SystemTimeError> {
+        Ok(current_time()? < expire_time)
+    }
+
+    pub fn get_id(&self) -> i64 {
+        self.id
+    }
+
+    pub fn get_expire_time(&self) -> u64 {
+        self.expire_time
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.is_available
     }
 
     pub fn get_delete_link(&self) -> &str {
         &self.delete_link
     }
+
+    pub fn get_server(&self) -> &str {
+        &self.server
+    }
+
+    pub fn get_link(&self) -> &str {
+        &self.link
+    }
+
+    pub fn get_sha256sum(&self) -> Option<&str> {
+        self.sha256sum.as_deref()
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+
+    pub fn is_password_protected(&self) -> bool {
+        self.password_protected
+    }
+
+    pub fn get_size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// Size shown in the tables, empty for entries uploaded before it was recorded.
+    pub fn readable_size(&self) -> String {
+        self.size.map(readable_size).unwrap_or_default()
+    }
+
+    pub fn get_content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    pub fn get_original_path(&self) -> Option<&str> {
+        self.original_path.as_deref()
+    }
+
+    pub fn get_server_status(&self) -> Option<u16> {
+        self.server_status
+    }
+
+    pub fn get_checked_time(&self) -> Option<u64> {
+        self.checked_time
+    }
+
+    pub(crate) fn set_check(&mut self, server_status: Option<u16>, checked_time: u64) {
+        self.server_status = server_status;
+        self.checked_time = Some(checked_time);
+    }
+
+    /// Result of the last check of the link, like `404 Not Found` or `unreachable`.
+    pub fn server_status_label(&self) -> String {
+        match (self.checked_time, self.server_status) {
+            (None, _) => String::from("not checked"),
+            (Some(_), None) => String::from("unreachable"),
+            (Some(_), Some(code)) => match StatusCode::from_u16(code) {
+                Ok(status) => status.to_string(),
+                Err(_) => code.to_string(),
+            },
+        }
+    }
+
+    /// Name shown in the tables, flagging entries that need a secret to be downloaded.
+    pub fn display_name(&self) -> String {
+        let mut display_name = self.name.clone();
+        if self.encrypted {
+            display_name.push_str(" [encrypted]");
+        }
+        if self.password_protected {
+            display_name.push_str(" [password]");
+        }
+        display_name
+    }
 }
 
 pub async fn get_file_size(path: &str) -> Result<String, TransferError> {
@@ -91,167 +503,115 @@ pub async fn get_file_size(path: &str) -> Result<String, TransferError> {
     if size > (2f64.powi(53) as u64) {
         return Err("File size is too large".into());
     }
-    let float_size = size as f64;
-    let kb = f64::from(1024);
-    let mb = f64::from(1024 * 1024);
-    let gb = f64::from(1024 * 1024 * 1024);
 
     match size {
         0 => Err("File is empty".into()),
-        1..=1023 => Ok(format!("{float_size} B")),
-        1024..=1_048_575 => Ok(format!("{:.2} KB", float_size / kb)),
-        1_048_576..=1_073_741_823 => Ok(format!("{:.2} MB", float_size / mb)),
-        1_073_741_824..=1_610_612_735 => Ok(format!("{:.2} GB", float_size / gb)),
+        1..=1_610_612_735 => Ok(readable_size(size)),
         _ => Err("File is over the 1.5GB limit".into()),
     }
 }
 
-pub fn get_config() -> Result<Config, TransferError> {
-    let config_path = config_app_folder()?.join("transfer-helper-config.json");
-    let default_config = Config::new();
+pub fn readable_size(size: u64) -> String {
+    let float_size = size as f64;
+    let kb = f64::from(1024);
+    let mb = f64::from(1024 * 1024);
+    let gb = f64::from(1024 * 1024 * 1024);
 
-    Ok(if let Ok(config) = read_to_string(&config_path) {
-        if let Ok(config) = serde_json::from_str(&config) {
-            config
-        } else {
-            write(config_path, serde_json::to_string_pretty(&default_config)?)?;
-            default_config
-        }
-    } else {
-        write(config_path, serde_json::to_string_pretty(&default_config)?)?;
-        default_config
-    })
+    match size {
+        0..=1023 => format!("{float_size} B"),
+        1024..=1_048_575 => format!("{:.2} KB", float_size / kb),
+        1_048_576..=1_073_741_823 => format!("{:.2} MB", float_size / mb),
+        _ => format!("{:.2} GB", float_size / gb),
+    }
 }
 
 pub fn current_time() -> Result<u64, SystemTimeError> {
     Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
 }
 
-pub fn create_config_app_folder() -> Result<(), io::Error> {
-    create_dir_all(config_app_folder()?)?;
-    Ok(())
-}
-
-pub fn config_app_folder() -> Result<PathBuf, io::Error> {
-    let config_path = config_dir().ok_or(io::Error::new(
-        io::ErrorKind::NotFound,
-        "Config directory not found",
-    ))?;
-    Ok(config_path.join("transfer-sh-helper"))
-}
-
-pub fn ask_confirmation(text: &str) -> Result<bool, io::Error> {
-    let mut confirmation = String::new();
-    print!("\n{} (y/N): ", text.yellow());
-    io::stdout().flush()?;
-    io::stdin().read_line(&mut confirmation)?;
-    println!();
-    Ok(confirmation.trim().to_lowercase().starts_with('y'))
-}
-
-pub async fn calculate_sha25sum(file_path: Arc<String>) -> Result<String, TransferError> {
-    let file = tokio::fs::File::open(file_path.as_ref()).await?;
-    let mut hasher = Sha256::new();
-    let mut reader = BufReader::new(file);
-    let mut buffer = Vec::new();
-    reader.read_to_end(&mut buffer).await?;
-    hasher.update(buffer);
-    let sha256sum = format!("{:x}", hasher.finalize());
-
-    Ok(sha256sum)
-}
-
-pub async fn upload_file(file_path: Arc<String>) -> Result<TransferResponse, TransferError> {
-    let file = tokio::fs::File::open(file_path.as_ref()).await?;
-    let total_size = file.metadata().await?.len();
-    let mut reader_stream = ReaderStream::new(file);
-    let mut total_uploaded = 0_f64;
-
-    let async_stream = async_stream::stream! {
-        while let Some(chunk) = reader_stream.next().await {
-            if let Ok(chunk) = &chunk {
-                total_uploaded += chunk.len() as f64;
-                let progress = (total_uploaded / total_size as f64) * 100.0;
-                print!("\rUploading... {:.2}%", progress.green());
-                io::stdout().flush()?;
+/// Looks up the `login` and `password` of `host` in the netrc file, falling back to its `default` entry.
+fn netrc_credentials(host: &str) -> Option<(String, Option<String>)> {
+    let netrc_path = env::var_os("NETRC")
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".netrc")))?;
+    let netrc = read_to_string(netrc_path).ok()?;
+
+    let mut tokens = netrc.split_whitespace();
+    let mut in_entry = false;
+    let mut login = None;
+    let mut password = None;
+    while let Some(token) = tokens.next() {
+        match token {
+            "machine" | "default" => {
+                if in_entry && login.is_some() {
+                    break;
+                }
+                in_entry = token == "default" || tokens.next() == Some(host);
+                login = None;
+                password = None;
             }
-            yield chunk;
+            "login" => login = tokens.next().filter(|_| in_entry),
+            "password" => password = tokens.next().filter(|_| in_entry),
+            _ => {}
         }
-    };
-
-    let response = reqwest::Client::new()
-        .put(&format!(
-            "https://transfer.sh/{}",
-            file_path
-                .as_ref()
-                .split('/')
-                .last()
-                .ok_or("Failed to get file name from upload URL.")?
-        ))
-        .body(reqwest::Body::wrap_stream(async_stream))
-        .send()
-        .await?;
-
-    if response.status() != StatusCode::OK {
-        return Err(format!("Failed to upload file. Status code: {}", response.status()).into());
-    }
-
-    println!("\n");
-
-    let delete_link = response
-        .headers()
-        .get("x-url-delete")
-        .ok_or("No delete link found.")?
-        .to_str()?
-        .to_owned();
-
-    Ok(TransferResponse {
-        transfer_link: response.text().await?,
-        delete_link,
-    })
-}
-
-pub fn output_data(list_del: bool, show_sha256: bool) -> Result<usize, TransferError> {
-    let data = DATABASE.try_lock()?.get_all_entries()?;
-
-    if data.is_empty() {
-        println!("No entries found.");
-        println!("Run `transferhelper -h` to see all available commands.\n");
-        exit(0);
     }
-    let data_len = data.len();
-    transfer_table!(data, list_del, show_sha256);
 
-    Ok(data_len)
+    if !in_entry {
+        return None;
+    }
+    Some((login?.to_string(), password.map(str::to_string)))
 }
 
-fn readable_date(unix_time: u64) -> Result<String, TransferError> {
-    let date = TimeZone::from_utc_datetime(
-        &Utc,
-        &NaiveDateTime::from_timestamp_opt((unix_time + UNIX_WEEK).try_into()?, 0)
-            .ok_or("Invalid date")?,
-    );
-    Ok(date.format("%d-%m-%Y").to_string())
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link() -> Link {
+        Link {
+            id: 1,
+            name: "file.txt".to_string(),
+            link: "https://transfer.sh/a/file.txt".to_string(),
+            delete_link: "https://transfer.sh/a/file.txt/del".to_string(),
+            upload_time: 1_700_000_000,
+            expire_time: 1_700_000_000 + UNIX_WEEK,
+            is_available: false,
+            sha256sum: Some("abc".to_string()),
+            server: DEFAULT_SERVER_URL.to_string(),
+            encrypted: false,
+            password_protected: false,
+            size: Some(3),
+            content_type: Some("text/plain".to_string()),
+            original_path: Some("/tmp/file.txt".to_string()),
+            server_status: Some(200),
+            checked_time: Some(1_700_000_100),
+        }
+    }
 
-pub async fn delete_entry_server(delete_link: &str) -> Result<Response, TransferError> {
-    let response = reqwest::Client::new().delete(delete_link).send().await?;
+    #[test]
+    fn fields_match_the_serialized_link() {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer.serialize(link()).unwrap();
+        let csv = String::from_utf8(writer.into_inner().unwrap()).unwrap();
 
-    match response.status() {
-        StatusCode::OK | StatusCode::NOT_FOUND => Ok(response),
-        _ => Err(format!("Failed to delete entry. Status code: {}", response.status()).into()),
+        assert_eq!(csv.lines().next(), Some(Link::FIELDS.join(",").as_str()));
     }
-}
 
-pub async fn transfer_response_code() -> Result<StatusCode, reqwest::Error> {
-    let transfer_link = "https://transfer.sh/";
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .build()?;
-
-    client
-        .get(transfer_link)
-        .send()
-        .await
-        .map(|response| response.status())
+    #[test]
+    fn credentials_stay_with_the_configured_server() {
+        env::set_var("NETRC", "/nonexistent/.netrc");
+        let mut config = Config {
+            server_url: "https://internal.example/".to_string(),
+            username: Some("alice".to_string()),
+            password: Some("s3cret".to_string()),
+            ..Config::default()
+        };
+        config.override_server_url("http://127.0.0.1:18555/".to_string());
+
+        assert_eq!(config.get_server_url(), "http://127.0.0.1:18555/");
+        assert_eq!(config.credentials_for("http://127.0.0.1:18555/b.txt"), None);
+        assert_eq!(
+            config.credentials_for("https://internal.example/a/b.txt"),
+            Some(("alice".to_string(), Some("s3cret".to_string())))
+        );
+    }
 }